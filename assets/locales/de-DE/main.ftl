@@ -0,0 +1,129 @@
+# Splash screen
+splash-start = Drücke { $key }, um die Suche zu beginnen
+splash-daily = Drücke { $key } für die Tagesaufgabe
+splash-replay = Drücke { $key }, um den letzten Lauf anzusehen
+splash-codex = Drücke { $key } für den Kodex
+splash-settings = Drücke { $key } für die Einstellungen
+splash-branches = Abzweigungen: { $branches } ({ $less }/{ $more })
+splash-difficulty = Zeitlimit: { $difficulty } ({ $harder }/{ $easier })
+difficulty-untimed = aus
+difficulty-easy = leicht
+difficulty-normal = normal
+difficulty-hard = schwer
+splash-granularity = Gabelungen: { $granularity } ({ $key } zum Ändern)
+granularity-letter = Buchstaben
+granularity-word = Wörter
+granularity-clause = Satzteile
+splash-language = Sprache: { $language } ({ $key } zum Wechseln)
+button-start = Start
+button-daily = Tagesaufgabe
+button-codex = Kodex
+button-replay = Aufzeichnung
+button-settings = Einstellungen
+button-back = Zurück
+button-play = Abspielen/Anhalten
+
+# Game screen
+game-seeking =
+    Drücke einen der folgenden Buchstaben,
+    vielleicht findest du die Wahrheit.
+game-seeking-word =
+    Tippe den Anfang eines der folgenden Wörter,
+    vielleicht findest du die Wahrheit.
+game-seeking-clause =
+    Tippe den Anfang eines der folgenden Satzteile,
+    vielleicht findest du die Wahrheit.
+game-hint = { $key } für einen Hinweis (kostet { $cost ->
+        [one] { $cost } Punkt
+       *[other] { $cost } Punkte
+    })
+game-fine =
+    Großartig.
+    Du hast die Wahrheit enthüllt.
+    Drücke eine beliebige Taste.
+game-tedious =
+    Moment...
+    Hast du nicht vielleicht
+    zu viele Buchstaben gedrückt?
+    Geh mit { $undo } zurück
+    oder fang mit { $back } neu an...
+
+# Fine screen
+fine-daily = Tagesaufgabe { $date }: in { $steps ->
+        [one] { $steps } Schritt
+       *[other] { $steps } Schritten
+    }
+fine-score = Punkte: { $score }
+fine-score-best = Punkte: { $score } (neue Bestleistung!)
+fine-score-details =
+    { $forks ->
+        [one] { $forks } Abzweigung
+       *[other] { $forks } Abzweigungen
+    }, { $wrong ->
+        [one] { $wrong } Irrweg
+       *[other] { $wrong } Irrwege
+    }, { $backtracks ->
+        [one] { $backtracks } Schritt zurück
+       *[other] { $backtracks } Schritte zurück
+    }, { $hints ->
+        [one] { $hints } Hinweis
+       *[other] { $hints } Hinweise
+    }, { $seconds } s
+fine-code = Lauf-Code: { $code }
+fine-new-game = Drücke { $key } für ein neues Spiel
+button-new-game = Neues Spiel
+
+# Replay screen
+replay-step = Aufzeichnung: Schritt { $step } von { $total }
+replay-desynced = Diese Aufzeichnung läuft nicht mehr wie aufgenommen ab
+replay-controls = { $left }/{ $right } zum Blättern, { $play } zum Abspielen, { $back } zum Verlassen
+
+# Codex screen
+codex-title = Kodex: { $found } von { $total } Wahrheiten enthüllt
+codex-entry = zuerst { $date }, Bestwert { $steps ->
+        [one] { $steps } Schritt
+       *[other] { $steps } Schritte
+    }, { $score ->
+        [one] { $score } Punkt
+       *[other] { $score } Punkte
+    }, { $reveals }-mal enthüllt
+codex-back = Drücke { $key }, um zurückzugehen
+
+# Settings screen
+settings-title = Einstellungen
+settings-controls = { $up }/{ $down } zum Auswählen, { $left }/{ $right } zum Ändern, { $confirm } zum Belegen, { $undo } zum Zurücksetzen, { $back } zum Speichern und Verlassen
+settings-waiting = Drücke eine Taste oder einen Gamepad-Knopf für { $action } oder Esc bzw. Select zum Abbrechen
+settings-text-size = Textgröße
+settings-theme = Design
+settings-accessible = Barrierefreier Modus
+settings-dyslexic-font = Legasthenie-freundliche Schrift
+settings-branches = Abzweigungen
+settings-difficulty = Zeitlimit
+settings-decoys = Falsche Wahlen
+decoys-uniform = zufällig
+decoys-frequency = häufig im Paket
+decoys-bigram = passend zur letzten Wahl
+decoys-trigram = passend zu den letzten zwei Wahlen
+settings-volume = Lautstärke
+settings-language = Sprache
+settings-on = an
+settings-off = aus
+action-confirm = Bestätigen
+action-back = Zurück
+action-undo = Schritt zurück
+action-hint = Hinweis
+action-choose-slot = Auswahl { $slot }
+action-left = Links
+action-right = Rechts
+action-up = Hoch
+action-down = Runter
+action-daily = Tagesaufgabe
+action-replay = Letzten Lauf ansehen
+action-codex = Kodex
+action-settings = Einstellungen
+action-granularity = Gabelungen
+action-language = Sprache
+action-play-pause = Aufzeichnung abspielen oder anhalten
+
+# Read out to screen readers
+announce-choice = Feld { $slot }: { $choice }
@@ -0,0 +1,128 @@
+# Splash screen
+splash-start = Press { $key } to start the seeking
+splash-daily = Press { $key } for the daily challenge
+splash-replay = Press { $key } to watch the last run
+splash-codex = Press { $key } to open the codex
+splash-settings = Press { $key } for the settings
+splash-branches = Forks: { $branches } ({ $less }/{ $more } to change)
+splash-difficulty = Timer: { $difficulty } ({ $harder }/{ $easier } to change)
+difficulty-untimed = off
+difficulty-easy = easy
+difficulty-normal = normal
+difficulty-hard = hard
+splash-granularity = Forks offer: { $granularity } ({ $key } to change)
+granularity-letter = letters
+granularity-word = words
+granularity-clause = clauses
+splash-language = Language: { $language } ({ $key } to change)
+button-start = Start
+button-daily = Daily
+button-codex = Codex
+button-replay = Replay
+button-settings = Settings
+button-back = Back
+button-play = Play/Pause
+
+# Game screen
+game-seeking =
+    Press one of the following letter
+    then you might find the truth.
+game-seeking-word =
+    Press the first letters of one of the following words
+    then you might find the truth.
+game-seeking-clause =
+    Press the first letters of one of the following clauses
+    then you might find the truth.
+game-hint = Press { $key } for a hint (costs { $cost ->
+        [one] { $cost } point
+       *[other] { $cost } points
+    })
+game-fine =
+    Great. You have revealed the truth.
+    Press any key to continue.
+game-tedious =
+    Wait...
+    Do you think you've pressed
+    too many letters?
+    Try press { $undo } to walk back,
+    or { $back } to start over...
+
+# Fine screen
+fine-daily = Daily { $date }: revealed in { $steps ->
+        [one] { $steps } step
+       *[other] { $steps } steps
+    }
+fine-score = Score: { $score }
+fine-score-best = Score: { $score } (new personal best!)
+fine-score-details =
+    { $forks ->
+        [one] { $forks } fork
+       *[other] { $forks } forks
+    }, { $wrong ->
+        [one] { $wrong } wrong turn
+       *[other] { $wrong } wrong turns
+    }, { $backtracks ->
+        [one] { $backtracks } step back
+       *[other] { $backtracks } steps back
+    }, { $hints ->
+        [one] { $hints } hint
+       *[other] { $hints } hints
+    }, { $seconds } s
+fine-code = Run code: { $code }
+fine-new-game = Press { $key } to start a new game
+button-new-game = New game
+
+# Replay screen
+replay-step = Replay: step { $step } of { $total }
+replay-desynced = This replay no longer plays back as recorded
+replay-controls = { $left }/{ $right } to step, { $play } to play, { $back } to leave
+
+# Codex screen
+codex-title = Codex: { $found } of { $total } truths revealed
+codex-entry = first { $date }, best { $steps ->
+        [one] { $steps } step
+       *[other] { $steps } steps
+    }, best score { $score }, revealed { $reveals ->
+        [one] once
+       *[other] { $reveals } times
+    }
+codex-back = Press { $key } to go back
+
+# Settings screen
+settings-title = Settings
+settings-controls = { $up }/{ $down } to pick a line, { $left }/{ $right } to change it, { $confirm } to bind a key, { $undo } to reset it, { $back } to save and go back
+settings-waiting = Press a key or gamepad button for { $action }, or Esc or Select to cancel
+settings-text-size = Text size
+settings-theme = Theme
+settings-accessible = Accessibility mode
+settings-dyslexic-font = Dyslexia-friendly font
+settings-branches = Forks
+settings-difficulty = Timer
+settings-decoys = Decoys
+decoys-uniform = random
+decoys-frequency = common in the pack
+decoys-bigram = likely after the last choice
+decoys-trigram = likely after the last two choices
+settings-volume = Sound volume
+settings-language = Language
+settings-on = on
+settings-off = off
+action-confirm = Confirm
+action-back = Back
+action-undo = Walk back
+action-hint = Hint
+action-choose-slot = Choice { $slot }
+action-left = Left
+action-right = Right
+action-up = Up
+action-down = Down
+action-daily = Daily challenge
+action-replay = Watch the last run
+action-codex = Codex
+action-settings = Settings
+action-granularity = Fork size
+action-language = Language
+action-play-pause = Play or pause a replay
+
+# Read out to screen readers
+announce-choice = slot { $slot }: { $choice }
@@ -0,0 +1,102 @@
+# Splash screen
+splash-start = 按 { $key } 键开始寻找
+splash-daily = 按 { $key } 键进行每日挑战
+splash-replay = 按 { $key } 键观看上一局
+splash-codex = 按 { $key } 键打开图鉴
+splash-settings = 按 { $key } 键打开设置
+splash-branches = 岔路：{ $branches }（按 { $less }/{ $more } 键调整）
+splash-difficulty = 限时：{ $difficulty }（按 { $harder }/{ $easier } 键调整）
+difficulty-untimed = 关闭
+difficulty-easy = 简单
+difficulty-normal = 普通
+difficulty-hard = 困难
+splash-granularity = 分岔：{ $granularity }（按 { $key } 键调整）
+granularity-letter = 字
+granularity-word = 词
+granularity-clause = 分句
+splash-language = 语言：{ $language }（按 { $key } 键切换）
+button-start = 开始
+button-daily = 每日挑战
+button-codex = 图鉴
+button-replay = 回放
+button-settings = 设置
+button-back = 返回
+button-play = 播放/暂停
+
+# Game screen
+game-seeking =
+    按下其中一个字母，
+    你也许就能找到真相。
+game-seeking-word =
+    输入其中一个词的开头，
+    你也许就能找到真相。
+game-seeking-clause =
+    输入其中一个分句的开头，
+    你也许就能找到真相。
+game-hint = 按 { $key } 键获取提示（扣 { $cost } 分）
+game-fine =
+    很好，你揭示了真相。
+    按任意键继续。
+game-tedious =
+    等等……
+    你是不是按了太多字母？
+    试试按 { $undo } 键往回走，
+    或按 { $back } 键重新开始……
+
+# Fine screen
+fine-daily = 每日挑战 { $date }：用了 { $steps } 步
+fine-score = 得分：{ $score }
+fine-score-best = 得分：{ $score }（新的个人最佳！）
+fine-score-details = { $forks } 个岔路，走错 { $wrong } 次，后退 { $backtracks } 步，提示 { $hints } 次，用时 { $seconds } 秒
+fine-code = 对局代码：{ $code }
+fine-new-game = 按 { $key } 键开始新游戏
+button-new-game = 新游戏
+
+# Replay screen
+replay-step = 回放：第 { $step } 步，共 { $total } 步
+replay-desynced = 此回放已无法按录制时的过程重现
+replay-controls = { $left }/{ $right } 键逐步查看，{ $play } 键播放，{ $back } 键退出
+
+# Codex screen
+codex-title = 图鉴：已揭示 { $found } / { $total } 条真相
+codex-entry = 首次 { $date }，最少 { $steps } 步，最高 { $score } 分，揭示 { $reveals } 次
+codex-back = 按 { $key } 键返回
+
+# Settings screen
+settings-title = 设置
+settings-controls = { $up }/{ $down } 键选择，{ $left }/{ $right } 键调整，{ $confirm } 键绑定按键，{ $undo } 键恢复默认，{ $back } 键保存并返回
+settings-waiting = 请按下用于“{ $action }”的按键或手柄按钮，或按 Esc 或 Select 取消
+settings-text-size = 文字大小
+settings-theme = 主题
+settings-accessible = 无障碍模式
+settings-dyslexic-font = 阅读障碍友好字体
+settings-branches = 岔路
+settings-difficulty = 限时
+settings-decoys = 干扰项
+decoys-uniform = 随机
+decoys-frequency = 常见于词库
+decoys-bigram = 承接上一个选择
+decoys-trigram = 承接前两个选择
+settings-volume = 音量
+settings-language = 语言
+settings-on = 开
+settings-off = 关
+action-confirm = 确认
+action-back = 返回
+action-undo = 后退
+action-hint = 提示
+action-choose-slot = 选项 { $slot }
+action-left = 左
+action-right = 右
+action-up = 上
+action-down = 下
+action-daily = 每日挑战
+action-replay = 观看上一局
+action-codex = 图鉴
+action-settings = 设置
+action-granularity = 分岔大小
+action-language = 语言
+action-play-pause = 播放或暂停回放
+
+# Read out to screen readers
+announce-choice = 选项 { $slot }：{ $choice }
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Write;
+
+/// The id of the ARIA live region of `index.html` announcements are written
+/// to on the web.
+#[cfg(target_arch = "wasm32")]
+const LIVE_REGION: &str = "announcer";
+
+/// Passes what the screen shows on to screen readers and speech synthesis:
+/// through an ARIA live region on the web, and on desktop through stdout or
+/// a file such as a named pipe when asked to with `--announce`.
+pub struct AnnouncePlugin;
+
+impl Plugin for AnnouncePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Announcement>()
+            .insert_resource(Announcer::new(requested_channel()))
+            .add_system_to_stage(CoreStage::PostUpdate, announce);
+    }
+}
+
+/// A line of text to be read out, sent when what the screen shows changes.
+pub struct Announcement(pub String);
+
+/// The lines of `text` that differ from the line in the same place of
+/// `shown`, the text on screen so far, so that only what changed is
+/// announced again.
+pub fn changed_lines<'a>(shown: &str, text: &'a str) -> Vec<&'a str> {
+    let mut shown = shown.lines();
+    text.lines()
+        .filter(|line| shown.next() != Some(*line))
+        .collect()
+}
+
+/// Where announcements go on desktop.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+#[derive(Clone, Debug, PartialEq)]
+enum Channel {
+    Stdout,
+    File(String),
+}
+
+struct Announcer {
+    #[cfg(not(target_arch = "wasm32"))]
+    out: Option<Box<dyn Write + Send + Sync>>,
+}
+
+impl Announcer {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn new(channel: Option<Channel>) -> Self {
+        let out: Option<Box<dyn Write + Send + Sync>> = match channel {
+            None => None,
+            Some(Channel::Stdout) => Some(Box::new(std::io::stdout())),
+            Some(Channel::File(path)) => match std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(&path)
+            {
+                Ok(file) => Some(Box::new(file)),
+                Err(error) => {
+                    warn!("Could not open {} for announcements: {}", path, error);
+                    None
+                }
+            },
+        };
+        Self { out }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn new(_channel: Option<Channel>) -> Self {
+        Self {}
+    }
+
+    /// Hands `text` on, a line at a time on desktop.
+    fn say(&mut self, text: &str) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let out = match &mut self.out {
+                Some(out) => out,
+                None => return,
+            };
+            let line = text.replace('\n', " ");
+            if let Err(error) = writeln!(out, "{}", line).and_then(|_| out.flush()) {
+                warn!("Could not announce, giving up: {}", error);
+                self.out = None;
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let region = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.get_element_by_id(LIVE_REGION));
+            if let Some(region) = region {
+                region.set_text_content(Some(text));
+            }
+        }
+    }
+}
+
+/// Reads where to announce to from `--announce`, for stdout, or
+/// `--announce=<path>` on desktop. The web build always announces.
+fn requested_channel() -> Option<Channel> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        channel_from_args(std::env::args().skip(1))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn channel_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<Channel> {
+    args.into_iter().find_map(|arg| {
+        if arg == "--announce" {
+            return Some(Channel::Stdout);
+        }
+        arg.strip_prefix("--announce=")
+            .filter(|path| !path.is_empty())
+            .map(|path| Channel::File(path.to_string()))
+    })
+}
+
+/// Announces what was sent this frame. On the web the lines of a frame
+/// replace the content of the live region together, as it is read out as a
+/// whole.
+fn announce(mut announcer: ResMut<Announcer>, mut announcements: EventReader<Announcement>) {
+    let lines = announcements
+        .iter()
+        .map(|announcement| announcement.0.as_str())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        return;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        for line in lines {
+            announcer.say(line);
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        announcer.say(&lines.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn reads_the_channel_from_args() {
+        assert_eq!(channel_from_args(args("--announce")), Some(Channel::Stdout));
+        assert_eq!(
+            channel_from_args(args("--seed 7 --announce=/tmp/ashgrove")),
+            Some(Channel::File("/tmp/ashgrove".to_string()))
+        );
+        assert_eq!(channel_from_args(args("--announce=")), None);
+        assert_eq!(channel_from_args(args("--seed 7")), None);
+    }
+
+    #[test]
+    fn announces_the_lines_that_changed() {
+        assert_eq!(changed_lines("", "Start\nDaily"), ["Start", "Daily"]);
+        assert_eq!(
+            changed_lines("Choices: 2\nEasy", "Choices: 3\nEasy"),
+            ["Choices: 3"]
+        );
+        assert_eq!(changed_lines("Start", "Start\nDaily"), ["Daily"]);
+        assert!(changed_lines("Start\nDaily", "Start\nDaily").is_empty());
+    }
+}
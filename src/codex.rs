@@ -0,0 +1,296 @@
+use super::announce::Announcement;
+use super::controls::{spawn_corner_buttons, Action, Bindings};
+use super::data::{PhrasePack, Phrases};
+use super::locale::Localization;
+use super::preferences::Preferences;
+use super::storage;
+use super::theme::{FontRole, Theme};
+use super::utils::{format_day, graphemes, is_inputable, unix_day};
+use super::{despawn_screen, GameState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const CODEX_FILE: &str = "codex.ron";
+
+/// Shown in place of each letter of a truth not revealed yet.
+const REDACTED: &str = "█";
+
+pub struct CodexPlugin;
+
+impl Plugin for CodexPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Codex::load())
+            .add_system_set(SystemSet::on_enter(GameState::Codex).with_system(codex_setup))
+            .add_system_set(SystemSet::on_update(GameState::Codex).with_system(leave_codex))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Codex).with_system(despawn_screen::<OnCodexScreen>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct OnCodexScreen;
+
+/// Every truth the player has revealed, kept across sessions.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Codex {
+    /// Keyed by phrase id, so that a truth revealed in one locale's pack
+    /// counts for the others too.
+    entries: BTreeMap<String, CodexEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CodexEntry {
+    /// Days since 1970-01-01 when the truth was first revealed.
+    pub first_revealed: u64,
+    pub best_steps: usize,
+    #[serde(default)]
+    pub best_score: u32,
+    pub reveals: u32,
+}
+
+impl Codex {
+    /// Loads the saved codex, or an empty one if there is none yet.
+    pub fn load() -> Self {
+        let text = match storage::load(CODEX_FILE) {
+            Some(text) => text,
+            None => return Self::default(),
+        };
+        ron::de::from_str(&text).unwrap_or_else(|error| {
+            warn!("Could not read the codex, starting a new one: {}", error);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        storage::save(CODEX_FILE, &text)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CodexEntry> {
+        self.entries.get(id)
+    }
+
+    /// Notes that the phrase `id` was revealed in `steps` for `score` on day
+    /// `day`, and tells whether the score is a new personal best.
+    pub fn record(&mut self, id: &str, steps: usize, score: u32, day: u64) -> bool {
+        let entry = self.entries.entry(id.to_string()).or_insert(CodexEntry {
+            first_revealed: day,
+            best_steps: steps,
+            best_score: 0,
+            reveals: 0,
+        });
+        let best = entry.reveals == 0 || score > entry.best_score;
+        entry.best_steps = entry.best_steps.min(steps);
+        entry.best_score = entry.best_score.max(score);
+        entry.reveals += 1;
+        best
+    }
+
+    /// Records a reveal made today and saves the codex straight away.
+    pub fn record_today(&mut self, id: &str, steps: usize, score: u32) -> bool {
+        let best = self.record(id, steps, score, unix_day());
+        if let Err(error) = self.save() {
+            warn!("Could not save the codex: {}", error);
+        }
+        best
+    }
+}
+
+/// Hides every letter of `text` while keeping its shape.
+fn redact(text: &str) -> String {
+    graphemes(text)
+        .iter()
+        .map(|grapheme| {
+            if is_inputable(grapheme) {
+                REDACTED
+            } else {
+                grapheme.as_str()
+            }
+        })
+        .collect()
+}
+
+fn codex_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    codex: Res<Codex>,
+    phrases: Res<Phrases>,
+    packs: Res<Assets<PhrasePack>>,
+    localization: Res<Localization>,
+    bindings: Res<Bindings>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+    mut announcements: EventWriter<Announcement>,
+) {
+    let style = |role: FontRole, size: f32| {
+        theme.text_style(
+            role,
+            size,
+            theme.palette.text,
+            &asset_server,
+            &localization,
+            &preferences,
+        )
+    };
+
+    let pack = phrases.get(&packs).unwrap();
+    let mut found = 0;
+    let mut revealed = Vec::new();
+    let mut lines = Vec::new();
+    for phrase in pack.phrases.iter() {
+        match codex.get(&phrase.id) {
+            Some(entry) => {
+                found += 1;
+                let date = format_day(entry.first_revealed);
+                let steps = entry.best_steps.to_string();
+                let score = entry.best_score.to_string();
+                let reveals = entry.reveals.to_string();
+                let details = localization.format(
+                    "codex-entry",
+                    &[
+                        ("date", date.as_str()),
+                        ("steps", steps.as_str()),
+                        ("score", score.as_str()),
+                        ("reveals", reveals.as_str()),
+                    ],
+                );
+                revealed.push(phrase.text.clone());
+                revealed.push(details.clone());
+                lines.push(phrase.text.clone());
+                lines.push(details);
+            }
+            None => {
+                lines.push(redact(&phrase.text));
+                lines.push(String::new());
+            }
+        }
+    }
+    let found = found.to_string();
+    let total = pack.phrases.len().to_string();
+    let title = localization.format(
+        "codex-title",
+        &[("found", found.as_str()), ("total", total.as_str())],
+    );
+    let back = bindings.key_name(Action::Back);
+    let back = localization.format("codex-back", &[("key", back.as_str())]);
+
+    // The truths not revealed yet are left out, as their redacted letters
+    // read out as nothing.
+    announcements.send(Announcement(title.clone()));
+    for line in revealed {
+        announcements.send(Announcement(line));
+    }
+    announcements.send(Announcement(back.clone()));
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            color: theme.palette.background.into(),
+            ..Default::default()
+        })
+        .insert(OnCodexScreen)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        bottom: Val::Px(theme.margins.title),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    title,
+                    style(FontRole::Title, theme.sizes.title),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    lines.join("\n"),
+                    style(FontRole::Body, theme.sizes.body),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position: Rect {
+                    bottom: Val::Px(theme.margins.footer),
+                    left: Val::Px(theme.margins.footer),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            text: Text::with_section(
+                back,
+                style(FontRole::Body, theme.sizes.body),
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(OnCodexScreen);
+
+    let buttons = spawn_corner_buttons(
+        &mut commands,
+        style(FontRole::Body, theme.sizes.info),
+        &theme,
+        &localization,
+        &[("button-back", Action::Back)],
+    );
+    commands.entity(buttons).insert(OnCodexScreen);
+}
+
+fn leave_codex(mut game_state: ResMut<State<GameState>>, actions: Res<Input<Action>>) {
+    if actions.just_released(Action::Back) || actions.just_released(Action::Confirm) {
+        game_state.set(GameState::Splash).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_reveals() {
+        let mut codex = Codex::default();
+        assert!(codex.record("healthy", 12, 600, 20_744));
+        assert!(codex.record("healthy", 9, 900, 20_745));
+        assert!(!codex.record("healthy", 15, 500, 20_746));
+        assert_eq!(
+            codex.get("healthy"),
+            Some(&CodexEntry {
+                first_revealed: 20_744,
+                best_steps: 9,
+                best_score: 900,
+                reveals: 3,
+            })
+        );
+        assert_eq!(codex.get("wealthy"), None);
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let mut codex = Codex::default();
+        codex.record("healthy", 12, 600, 20_744);
+        let text = ron::ser::to_string_pretty(&codex, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(ron::de::from_str::<Codex>(&text).unwrap(), codex);
+    }
+
+    #[test]
+    fn redacts_letters_only() {
+        assert_eq!(redact("be healthy"), "██ ███████");
+        assert_eq!(redact("Wish you luck!"), "████ ███ ████!");
+    }
+}
@@ -0,0 +1,623 @@
+use super::locale::Localization;
+use super::path::MAX_BRANCHES;
+use super::preferences::Preferences;
+use super::storage;
+use super::theme::{FontRole, Theme};
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const BINDINGS_FILE: &str = "bindings.ron";
+/// How far a pointer has to travel, in pixels, to swipe rather than tap.
+const SWIPE_DISTANCE: f32 = 40.0;
+
+/// Turns the keys, gamepad buttons, taps and swipes into [`Action`]s, read
+/// through `Input<Action>`.
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bindings::load())
+            .init_resource::<Input<Action>>()
+            .init_resource::<PointerStart>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions.label("update_actions").after(InputSystem),
+            )
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_pointer_actions
+                    .after("update_actions")
+                    .after(UiSystem::Focus),
+            )
+            .add_system(update_button_labels);
+    }
+}
+
+/// Presses its action while clicked or tapped, for players without a
+/// keyboard.
+#[derive(Component)]
+pub struct ActionButton(pub Action);
+
+/// The key of the localized label of an [`ActionButton`].
+#[derive(Component)]
+struct ButtonLabel(&'static str);
+
+/// Where the mouse was pressed, to tell the swipes made by dragging it.
+#[derive(Default)]
+struct PointerStart(Option<Vec2>);
+
+/// What the player asks for, whatever key is bound to it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Action {
+    Confirm,
+    Back,
+    /// Walks back up the path.
+    Undo,
+    Hint,
+    /// Takes the choice in the given slot, counted from 0.
+    ChooseSlot(usize),
+    Left,
+    Right,
+    Up,
+    Down,
+    Daily,
+    Replay,
+    Codex,
+    Settings,
+    Granularity,
+    Language,
+    PlayPause,
+}
+
+impl Action {
+    /// Every action, in the order the settings screen lists them.
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![Action::Confirm, Action::Back, Action::Undo, Action::Hint];
+        actions.extend((0..MAX_BRANCHES).map(Action::ChooseSlot));
+        actions.extend([
+            Action::Left,
+            Action::Right,
+            Action::Up,
+            Action::Down,
+            Action::Daily,
+            Action::Replay,
+            Action::Codex,
+            Action::Settings,
+            Action::Granularity,
+            Action::Language,
+            Action::PlayPause,
+        ]);
+        actions
+    }
+
+    /// The key of the localized name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::Confirm => "action-confirm",
+            Action::Back => "action-back",
+            Action::Undo => "action-undo",
+            Action::Hint => "action-hint",
+            Action::ChooseSlot(_) => "action-choose-slot",
+            Action::Left => "action-left",
+            Action::Right => "action-right",
+            Action::Up => "action-up",
+            Action::Down => "action-down",
+            Action::Daily => "action-daily",
+            Action::Replay => "action-replay",
+            Action::Codex => "action-codex",
+            Action::Settings => "action-settings",
+            Action::Granularity => "action-granularity",
+            Action::Language => "action-language",
+            Action::PlayPause => "action-play-pause",
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        const SLOT_KEYS: [KeyCode; MAX_BRANCHES] = [
+            KeyCode::Key1,
+            KeyCode::Key2,
+            KeyCode::Key3,
+            KeyCode::Key4,
+            KeyCode::Key5,
+            KeyCode::Key6,
+        ];
+
+        let key = match self {
+            Action::Confirm => KeyCode::Return,
+            Action::Back => KeyCode::Escape,
+            Action::Undo => KeyCode::Back,
+            Action::Hint => KeyCode::Tab,
+            Action::ChooseSlot(slot) => return SLOT_KEYS.get(*slot).copied().into_iter().collect(),
+            Action::Left => KeyCode::Left,
+            Action::Right => KeyCode::Right,
+            Action::Up => KeyCode::Up,
+            Action::Down => KeyCode::Down,
+            Action::Daily => KeyCode::D,
+            Action::Replay => KeyCode::R,
+            Action::Codex => KeyCode::C,
+            Action::Settings => KeyCode::S,
+            Action::Granularity => KeyCode::G,
+            Action::Language => KeyCode::Tab,
+            Action::PlayPause => KeyCode::Space,
+        };
+        vec![key]
+    }
+
+    fn default_buttons(&self) -> Vec<GamepadButtonType> {
+        use GamepadButtonType::*;
+
+        // Left to right across the shoulders and sticks, shared with the
+        // actions of the splash screen and replays, which runs never read.
+        const SLOT_BUTTONS: [GamepadButtonType; MAX_BRANCHES] = [
+            LeftTrigger2,
+            LeftTrigger,
+            LeftThumb,
+            RightThumb,
+            RightTrigger,
+            RightTrigger2,
+        ];
+
+        let button = match self {
+            Action::Confirm => South,
+            Action::Back => Select,
+            Action::Undo => East,
+            Action::Hint => North,
+            Action::ChooseSlot(slot) => {
+                return SLOT_BUTTONS.get(*slot).copied().into_iter().collect()
+            }
+            Action::Left => DPadLeft,
+            Action::Right => DPadRight,
+            Action::Up => DPadUp,
+            Action::Down => DPadDown,
+            Action::Daily => West,
+            Action::Replay => RightTrigger2,
+            Action::Codex => LeftTrigger2,
+            Action::Settings => Start,
+            Action::Granularity => RightTrigger,
+            Action::Language => LeftTrigger,
+            Action::PlayPause => RightThumb,
+        };
+        vec![button]
+    }
+}
+
+/// The keys and gamepad buttons bound to every action, kept across
+/// sessions.
+///
+/// Actions used on different screens may share keys and buttons, e.g. Tab
+/// asks for a hint in a run and switches the language on the splash screen.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct Bindings {
+    keys: BTreeMap<Action, Vec<KeyCode>>,
+    #[serde(default)]
+    buttons: BTreeMap<Action, Vec<GamepadButtonType>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let all = Action::all();
+        let keys = all
+            .iter()
+            .map(|action| (*action, action.default_keys()))
+            .collect();
+        let buttons = all
+            .iter()
+            .map(|action| (*action, action.default_buttons()))
+            .collect();
+        Self { keys, buttons }
+    }
+}
+
+impl Bindings {
+    /// Loads the saved bindings, or the default ones if there are none yet.
+    pub fn load() -> Self {
+        let text = match storage::load(BINDINGS_FILE) {
+            Some(text) => text,
+            None => return Self::default(),
+        };
+        Self::parse(&text).unwrap_or_else(|error| {
+            warn!(
+                "Could not read the key bindings, using the defaults: {}",
+                error
+            );
+            Self::default()
+        })
+    }
+
+    /// Parses saved bindings, binding the default keys and buttons to
+    /// actions they miss.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut bindings: Self = ron::de::from_str(text)?;
+        for action in Action::all() {
+            bindings
+                .keys
+                .entry(action)
+                .or_insert_with(|| action.default_keys());
+            bindings
+                .buttons
+                .entry(action)
+                .or_insert_with(|| action.default_buttons());
+        }
+        Ok(bindings)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        storage::save(BINDINGS_FILE, &text)
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `key` to `action`, in place of the keys bound to it so far.
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// Binds `button` to `action`, in place of the buttons bound to it so
+    /// far.
+    pub fn bind_button(&mut self, action: Action, button: GamepadButtonType) {
+        self.buttons.insert(action, vec![button]);
+    }
+
+    /// Binds the default keys and buttons to `action` again.
+    pub fn reset(&mut self, action: Action) {
+        self.keys.insert(action, action.default_keys());
+        self.buttons.insert(action, action.default_buttons());
+    }
+
+    /// The name of the first key bound to `action`, to tell players which
+    /// key to press.
+    pub fn key_name(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(|| "-".to_string(), |&key| key_name(key))
+    }
+}
+
+/// The name a key is shown by.
+pub fn key_name(key: KeyCode) -> String {
+    let name = match key {
+        KeyCode::Return => "Enter",
+        KeyCode::Escape => "Esc",
+        KeyCode::Back => "Backspace",
+        KeyCode::Key1 => "1",
+        KeyCode::Key2 => "2",
+        KeyCode::Key3 => "3",
+        KeyCode::Key4 => "4",
+        KeyCode::Key5 => "5",
+        KeyCode::Key6 => "6",
+        KeyCode::Key7 => "7",
+        KeyCode::Key8 => "8",
+        KeyCode::Key9 => "9",
+        KeyCode::Key0 => "0",
+        _ => return format!("{:?}", key),
+    };
+    name.to_string()
+}
+
+/// The name a gamepad button is shown by.
+pub fn button_name(button: GamepadButtonType) -> String {
+    format!("{:?}", button)
+}
+
+/// The actions a swipe across `distance` stands for, if it went far enough.
+///
+/// Swiping down also walks back up the path, as touch screens have no key
+/// for it.
+fn swipe_actions(distance: Vec2) -> &'static [Action] {
+    if distance.length() < SWIPE_DISTANCE {
+        return &[];
+    }
+    if distance.x.abs() > distance.y.abs() {
+        if distance.x > 0.0 {
+            &[Action::Right]
+        } else {
+            &[Action::Left]
+        }
+    } else if distance.y > 0.0 {
+        &[Action::Up]
+    } else {
+        &[Action::Down, Action::Undo]
+    }
+}
+
+/// Spawns a button labelled with the localized text at `key` in `style`,
+/// which presses `action` when tapped or clicked.
+pub fn spawn_action_button(
+    parent: &mut ChildBuilder,
+    style: TextStyle,
+    theme: &Theme,
+    localization: &Localization,
+    key: &'static str,
+    action: Action,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                // Big enough for a finger.
+                min_size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                margin: Rect::all(Val::Px(theme.margins.button)),
+                padding: Rect {
+                    left: Val::Px(16.0),
+                    right: Val::Px(16.0),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: theme.palette.button.into(),
+            ..Default::default()
+        })
+        .insert(ActionButton(action))
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(localization.get(key), style, Default::default()),
+                    ..Default::default()
+                })
+                .insert(ButtonLabel(key));
+        });
+}
+
+/// Spawns buttons for `actions`, given with the keys of their labels, in the
+/// bottom right corner of a screen that otherwise only reacts to keys and
+/// gamepads.
+pub fn spawn_corner_buttons(
+    commands: &mut Commands,
+    style: TextStyle,
+    theme: &Theme,
+    localization: &Localization,
+    actions: &[(&'static str, Action)],
+) -> Entity {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position: Rect {
+                    bottom: Val::Px(theme.margins.footer),
+                    right: Val::Px(theme.margins.footer),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for &(key, action) in actions {
+                spawn_action_button(parent, style.clone(), theme, localization, key, action);
+            }
+        })
+        .id()
+}
+
+/// Buttons of any gamepad count, so that whichever is picked up plays.
+fn update_actions(
+    bindings: Res<Bindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    actions.clear();
+    for action in Action::all() {
+        let bound_keys = bindings.keys(action);
+        let bound_buttons = bindings.buttons(action);
+        let is_bound = |button: &GamepadButton| bound_buttons.contains(&button.1);
+        if bound_keys.iter().any(|&key| keys.just_pressed(key))
+            || buttons.get_just_pressed().any(is_bound)
+        {
+            actions.press(action);
+        }
+        if bound_keys.iter().any(|&key| keys.just_released(key))
+            || buttons.get_just_released().any(is_bound)
+        {
+            actions.release(action);
+        }
+    }
+}
+
+/// Presses the actions of tapped buttons and of swipes, made by touch or by
+/// dragging the mouse.
+///
+/// Runs after `update_actions`, which clears the actions of the last frame.
+fn update_pointer_actions(
+    windows: Res<Windows>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut start: ResMut<PointerStart>,
+    buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    mut actions: ResMut<Input<Action>>,
+) {
+    for (&interaction, button) in buttons.iter() {
+        follow_interaction(interaction, button.0, &mut actions);
+    }
+
+    let cursor = windows.get_primary().and_then(Window::cursor_position);
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        start.0 = cursor;
+    }
+    let mut swipes = touches
+        .iter_just_released()
+        .map(Touch::distance)
+        .collect::<Vec<_>>();
+    if mouse_buttons.just_released(MouseButton::Left) {
+        if let (Some(start), Some(end)) = (start.0.take(), cursor) {
+            swipes.push(end - start);
+        }
+    }
+    for &action in swipes.into_iter().flat_map(swipe_actions) {
+        // A swipe is over at once, so its action is pressed and let go in the
+        // same frame.
+        actions.press(action);
+        actions.release(action);
+    }
+}
+
+/// Presses `action` when its button is clicked, and lets go of it once the
+/// click ends over the button. Dragging off the button cancels the click, and
+/// hovering alone does nothing.
+fn follow_interaction(interaction: Interaction, action: Action, actions: &mut Input<Action>) {
+    if interaction == Interaction::Clicked {
+        actions.press(action);
+    } else if actions.pressed(action) {
+        if interaction == Interaction::Hovered {
+            actions.release(action);
+        } else {
+            actions.reset(action);
+        }
+    }
+}
+
+/// Relabels and restyles the buttons when the language or the theme is
+/// switched.
+fn update_button_labels(
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+    mut labels: Query<(&mut Text, &ButtonLabel)>,
+    mut buttons: Query<&mut UiColor, With<ActionButton>>,
+) {
+    if !localization.is_changed() && !theme.is_changed() && !preferences.is_changed() {
+        return;
+    }
+    for (mut text, label) in labels.iter_mut() {
+        text.sections[0].value = localization.get(label.0).to_string();
+        text.sections[0].style = theme.text_style(
+            FontRole::Body,
+            theme.sizes.info,
+            theme.palette.text,
+            &asset_server,
+            &localization,
+            &preferences,
+        );
+    }
+    for mut color in buttons.iter_mut() {
+        *color = theme.palette.button.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binds_every_action_by_default() {
+        let bindings = Bindings::default();
+        for action in Action::all() {
+            assert!(!bindings.keys(action).is_empty(), "{:?}", action);
+            assert!(!bindings.buttons(action).is_empty(), "{:?}", action);
+        }
+        assert_eq!(bindings.keys(Action::ChooseSlot(0)), [KeyCode::Key1]);
+        assert_eq!(
+            bindings.buttons(Action::Confirm),
+            [GamepadButtonType::South]
+        );
+        assert_eq!(bindings.buttons(Action::Back), [GamepadButtonType::Select]);
+    }
+
+    #[test]
+    fn binds_different_buttons_to_the_actions_of_a_screen() {
+        let slots = (0..MAX_BRANCHES).map(Action::ChooseSlot);
+        let screens = [
+            [Action::Confirm, Action::Back, Action::Undo, Action::Hint]
+                .into_iter()
+                .chain(slots)
+                .chain([Action::Left, Action::Right, Action::Up])
+                .collect::<Vec<_>>(),
+            vec![
+                Action::Confirm,
+                Action::Daily,
+                Action::Replay,
+                Action::Codex,
+                Action::Settings,
+                Action::Granularity,
+                Action::Language,
+                Action::Left,
+                Action::Right,
+                Action::Up,
+                Action::Down,
+            ],
+            vec![Action::Back, Action::Left, Action::Right, Action::PlayPause],
+            vec![
+                Action::Back,
+                Action::Confirm,
+                Action::Undo,
+                Action::Left,
+                Action::Right,
+                Action::Up,
+                Action::Down,
+            ],
+        ];
+        let bindings = Bindings::default();
+        for screen in screens {
+            let mut seen = Vec::new();
+            for action in screen {
+                for button in bindings.buttons(action) {
+                    assert!(!seen.contains(button), "{:?} on {:?}", button, action);
+                    seen.push(*button);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Undo, KeyCode::Delete);
+        bindings.bind_button(Action::Hint, GamepadButtonType::LeftThumb);
+        let text = ron::ser::to_string(&bindings).unwrap();
+        assert_eq!(Bindings::parse(&text).unwrap(), bindings);
+    }
+
+    #[test]
+    fn fills_in_actions_missing_from_the_file() {
+        let bindings = Bindings::parse("(keys: {Hint: [H]})").unwrap();
+        assert_eq!(bindings.keys(Action::Hint), [KeyCode::H]);
+        assert_eq!(bindings.keys(Action::Confirm), [KeyCode::Return]);
+        assert_eq!(bindings.buttons(Action::Undo), [GamepadButtonType::East]);
+    }
+
+    #[test]
+    fn presses_buttons_on_click_only() {
+        let mut actions = Input::default();
+        follow_interaction(Interaction::Hovered, Action::Confirm, &mut actions);
+        follow_interaction(Interaction::None, Action::Confirm, &mut actions);
+        assert!(!actions.just_released(Action::Confirm));
+
+        follow_interaction(Interaction::Clicked, Action::Confirm, &mut actions);
+        assert!(actions.just_pressed(Action::Confirm));
+        actions.clear();
+        follow_interaction(Interaction::Hovered, Action::Confirm, &mut actions);
+        assert!(actions.just_released(Action::Confirm));
+
+        actions.clear();
+        follow_interaction(Interaction::Clicked, Action::Confirm, &mut actions);
+        actions.clear();
+        follow_interaction(Interaction::None, Action::Confirm, &mut actions);
+        assert!(!actions.pressed(Action::Confirm));
+        assert!(!actions.just_released(Action::Confirm));
+    }
+
+    #[test]
+    fn swipes_along_the_longer_axis() {
+        assert!(swipe_actions(Vec2::new(5.0, -3.0)).is_empty());
+        assert_eq!(swipe_actions(Vec2::new(60.0, 20.0)), [Action::Right]);
+        assert_eq!(swipe_actions(Vec2::new(-60.0, 20.0)), [Action::Left]);
+        assert_eq!(swipe_actions(Vec2::new(10.0, 80.0)), [Action::Up]);
+        assert_eq!(
+            swipe_actions(Vec2::new(10.0, -80.0)),
+            [Action::Down, Action::Undo]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use super::decoy::DecoyKind;
+use super::path::Granularity;
+use super::utils::{format_day, unix_day};
+
+/// Mixed into the day so that daily seeds stay apart from typed ones.
+const SEED_SALT: u64 = 0x6173_6867_726f_7665;
+
+/// Every daily challenge is played with the same number of branches, so that
+/// all players walk the same forks whatever their settings.
+pub const DAILY_BRANCHES: usize = 3;
+
+/// Daily challenges always fork by letter, for the same reason.
+pub const DAILY_GRANULARITY: Granularity = Granularity::Letter;
+
+/// And with the same decoys.
+pub const DAILY_DECOYS: DecoyKind = DecoyKind::Trigram;
+
+/// The daily challenge of a UTC date.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Daily {
+    /// Days since 1970-01-01.
+    day: u64,
+}
+
+impl Daily {
+    pub fn today() -> Self {
+        Self { day: unix_day() }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.day ^ SEED_SALT
+    }
+
+    /// The date as `YYYY-MM-DD`.
+    pub fn date(&self) -> String {
+        format_day(self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_the_date() {
+        assert_eq!(Daily { day: 0 }.date(), "1970-01-01");
+        assert_eq!(Daily { day: 59 }.date(), "1970-03-01");
+        assert_eq!(Daily { day: 11_016 }.date(), "2000-02-29");
+        assert_eq!(Daily { day: 20_744 }.date(), "2026-10-18");
+    }
+
+    #[test]
+    fn seeds_differ_from_day_to_day() {
+        assert_ne!(Daily { day: 20_744 }.seed(), Daily { day: 20_745 }.seed());
+    }
+}
@@ -1,13 +1,126 @@
-pub const DATA: [&str; 11] = [
-    "be healthy",
-    "be wealthy",
-    "find a good job",
-    "find your soulmate",
-    "happy new year",
-    "live lightheartedly",
-    "pursue your dreams",
-    "Closure is a record storing\na function together with an\nenvironment",
-    "Closure is a technique for\nimplementing lexically scoped\nname binding",
-    "Closure is used in a language\nwith first class functions",
-    "Closure is used in Javascript,\nGolang, Rust, Python etc",
-];
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const DEFAULT_PACK_PATH: &str = "phrases/default.phrases.ron";
+
+pub struct DataPlugin;
+
+impl Plugin for DataPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<PhrasePack>()
+            .init_asset_loader::<PhrasePackLoader>()
+            .add_startup_system(load_phrases);
+    }
+}
+
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "c6535dfa-923a-4387-bdaf-00c29d479f81"]
+pub struct PhrasePack {
+    pub name: String,
+    /// Bumped whenever phrases are edited, so that replays tell which
+    /// revision of the pack they were recorded with.
+    #[serde(default)]
+    pub version: u32,
+    pub phrases: Vec<Phrase>,
+    /// Overrides the built-in reveal template of a kind for the whole pack.
+    #[serde(default)]
+    pub templates: HashMap<PhraseKind, String>,
+}
+
+impl PhrasePack {
+    /// Builds the text shown on the Fine screen once `phrase` is revealed.
+    ///
+    /// The template is picked from the phrase itself, then the pack, then the
+    /// kind's default. `{phrase}`, `{author}` and `{steps}` are substituted.
+    pub fn reveal_text(&self, phrase: &Phrase, steps: usize) -> String {
+        let template = phrase
+            .template
+            .as_deref()
+            .or_else(|| self.templates.get(&phrase.kind).map(String::as_str))
+            .unwrap_or_else(|| phrase.kind.default_template());
+
+        template
+            .replace("{phrase}", &phrase.text)
+            .replace("{author}", phrase.author.as_deref().unwrap_or_default())
+            .replace("{steps}", &steps.to_string())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Phrase {
+    pub id: String,
+    pub kind: PhraseKind,
+    pub text: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq)]
+pub enum PhraseKind {
+    Wish,
+    Fact,
+    Quote,
+    Question,
+}
+
+impl PhraseKind {
+    pub fn default_template(&self) -> &'static str {
+        match self {
+            PhraseKind::Wish => "Wish you {phrase}.",
+            PhraseKind::Fact => "{phrase}.",
+            PhraseKind::Quote => "\"{phrase}\"\n- {author}",
+            PhraseKind::Question => "{phrase}?",
+        }
+    }
+}
+
+/// Handle to the phrase pack the game picks its phrases from.
+pub struct Phrases {
+    pub pack: Handle<PhrasePack>,
+}
+
+impl Phrases {
+    /// Returns the pack once it has finished loading.
+    pub fn get<'a>(&self, packs: &'a Assets<PhrasePack>) -> Option<&'a PhrasePack> {
+        packs.get(&self.pack)
+    }
+}
+
+#[derive(Default)]
+pub struct PhrasePackLoader;
+
+impl AssetLoader for PhrasePackLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let pack = ron::de::from_bytes::<PhrasePack>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(pack));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["phrases.ron"]
+    }
+}
+
+fn load_phrases(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Let writers tweak the phrases without restarting the game.
+    #[cfg(all(debug_assertions, not(target_arch = "wasm32")))]
+    asset_server.watch_for_changes().unwrap();
+
+    commands.insert_resource(Phrases {
+        pack: asset_server.load(DEFAULT_PACK_PATH),
+    });
+}
@@ -0,0 +1,373 @@
+use super::path::Granularity;
+use super::utils::{is_inputable, to_lowercase};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+/// Picks the letters, or words or clauses, offered beside the true one at
+/// every fork.
+pub trait DecoyStrategy: Debug + Send + Sync {
+    /// Picks a decoy to offer after `context`, the text walked so far split
+    /// and lowercased like the phrases, that is none of `taken`.
+    fn decoy(&self, context: &[String], taken: &[String], rng: &mut dyn RngCore) -> String;
+}
+
+/// The decoy strategies a run can be played with.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum DecoyKind {
+    Uniform,
+    Frequency,
+    Bigram,
+    #[default]
+    Trigram,
+}
+
+impl DecoyKind {
+    pub const ALL: [DecoyKind; 4] = [
+        DecoyKind::Uniform,
+        DecoyKind::Frequency,
+        DecoyKind::Bigram,
+        DecoyKind::Trigram,
+    ];
+
+    /// The key of the localized name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            DecoyKind::Uniform => "decoys-uniform",
+            DecoyKind::Frequency => "decoys-frequency",
+            DecoyKind::Bigram => "decoys-bigram",
+            DecoyKind::Trigram => "decoys-trigram",
+        }
+    }
+
+    /// The next strategy, wrapping back to uniform.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|kind| kind == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Builds the strategy for forks of `granularity`, trained on `phrases`
+    /// where it needs to be.
+    ///
+    /// There is no drawing words and clauses uniformly, so they are drawn by
+    /// frequency instead.
+    pub fn build<S: AsRef<str>>(&self, phrases: &[S], granularity: Granularity) -> Decoys {
+        match self {
+            DecoyKind::Uniform if granularity == Granularity::Letter => Decoys::new(Uniform),
+            DecoyKind::Uniform | DecoyKind::Frequency => {
+                Decoys::new(Frequency::train(phrases, granularity))
+            }
+            DecoyKind::Bigram => Decoys::new(Markov::train(phrases, granularity, 1)),
+            DecoyKind::Trigram => Decoys::new(Markov::train(phrases, granularity, 2)),
+        }
+    }
+}
+
+/// A shared decoy strategy, uniform unless told otherwise.
+#[derive(Clone, Debug)]
+pub struct Decoys(Arc<dyn DecoyStrategy>);
+
+impl Decoys {
+    pub fn new<D: DecoyStrategy + 'static>(strategy: D) -> Self {
+        Self(Arc::new(strategy))
+    }
+
+    pub fn decoy(&self, context: &[String], taken: &[String], rng: &mut dyn RngCore) -> String {
+        self.0.decoy(context, taken, rng)
+    }
+}
+
+impl Default for Decoys {
+    fn default() -> Self {
+        Self::new(Uniform)
+    }
+}
+
+/// Any of `a` to `z`, all as likely.
+#[derive(Debug)]
+pub struct Uniform;
+
+impl DecoyStrategy for Uniform {
+    fn decoy(&self, _context: &[String], taken: &[String], rng: &mut dyn RngCore) -> String {
+        let letters = ('a'..='z')
+            .map(String::from)
+            .filter(|letter| !taken.contains(letter))
+            .collect::<Vec<_>>();
+        letters[rng.gen_range(0..letters.len())].clone()
+    }
+}
+
+/// Letters, or words or clauses, as often as they appear in the phrases, so
+/// that decoys come from the script of the pack.
+///
+/// Once every word or clause of the phrases is taken, words are made up from
+/// the letters of the phrases, so that decoys are never single letters.
+#[derive(Debug, Default)]
+pub struct Frequency {
+    counts: Vec<(String, u32)>,
+    granularity: Granularity,
+    /// The letters of the phrases, to make words up from.
+    letters: Vec<(String, u32)>,
+}
+
+impl Frequency {
+    pub fn train<S: AsRef<str>>(phrases: &[S], granularity: Granularity) -> Self {
+        let mut frequency = Self {
+            granularity,
+            ..Self::default()
+        };
+        for phrase in phrases {
+            for unit in granularity.split(phrase.as_ref()) {
+                if is_inputable(&unit) {
+                    add_count(&mut frequency.counts, to_lowercase(&unit));
+                }
+            }
+            if granularity != Granularity::Letter {
+                for letter in Granularity::Letter.split(phrase.as_ref()) {
+                    if is_inputable(&letter) {
+                        add_count(&mut frequency.letters, to_lowercase(&letter));
+                    }
+                }
+            }
+        }
+        frequency
+    }
+
+    /// A word, or a clause of two to four words, made up of letters drawn by
+    /// their frequency, that is none of `taken`.
+    fn made_up(&self, taken: &[String], rng: &mut dyn RngCore) -> String {
+        let words = if self.granularity == Granularity::Clause {
+            rng.gen_range(2..=4)
+        } else {
+            1
+        };
+        // Longer words every few attempts, so that one is eventually free
+        // however few letters the phrases have.
+        for attempt in 0.. {
+            let text = (0..words)
+                .map(|_| {
+                    let len = rng.gen_range(2..=7) + attempt / 8;
+                    (0..len)
+                        .map(|_| {
+                            weighted_pick(&self.letters, &[], rng)
+                                .unwrap_or_else(|| Uniform.decoy(&[], &[], rng))
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !taken.contains(&text) {
+                return text;
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl DecoyStrategy for Frequency {
+    fn decoy(&self, context: &[String], taken: &[String], rng: &mut dyn RngCore) -> String {
+        weighted_pick(&self.counts, taken, rng).unwrap_or_else(|| match self.granularity {
+            Granularity::Letter => Uniform.decoy(context, taken, rng),
+            Granularity::Word | Granularity::Clause => self.made_up(taken, rng),
+        })
+    }
+}
+
+/// Letters, or words or clauses, as often as they follow the last few ones
+/// in the phrases, so that decoys plausibly continue the text.
+///
+/// Contexts never seen in the phrases back off to shorter ones, then to the
+/// letter frequency.
+#[derive(Debug)]
+pub struct Markov {
+    /// How many units of context are looked at, at most.
+    order: usize,
+    /// The units following every context of 1 to `order` units.
+    follows: HashMap<Vec<String>, Vec<(String, u32)>>,
+    frequency: Frequency,
+    granularity: Granularity,
+}
+
+impl Markov {
+    pub fn train<S: AsRef<str>>(phrases: &[S], granularity: Granularity, order: usize) -> Self {
+        let mut follows = HashMap::new();
+        for phrase in phrases {
+            let units = granularity.split(&phrase.as_ref().to_lowercase());
+            let text = context_of(&units, granularity);
+            for (end, next) in text.iter().enumerate().skip(1) {
+                if !is_inputable(next) {
+                    continue;
+                }
+                for len in 1..=order.min(end) {
+                    let key = text[end - len..end].to_vec();
+                    add_count(follows.entry(key).or_default(), next.clone());
+                }
+            }
+        }
+        Self {
+            order,
+            follows,
+            frequency: Frequency::train(phrases, granularity),
+            granularity,
+        }
+    }
+}
+
+impl DecoyStrategy for Markov {
+    fn decoy(&self, context: &[String], taken: &[String], rng: &mut dyn RngCore) -> String {
+        let context = context_of(context, self.granularity);
+        for len in (1..=self.order.min(context.len())).rev() {
+            let key = &context[context.len() - len..];
+            if let Some(counts) = self.follows.get(key) {
+                if let Some(decoy) = weighted_pick(counts, taken, rng) {
+                    return decoy;
+                }
+            }
+        }
+        self.frequency.decoy(&context, taken, rng)
+    }
+}
+
+/// The units a Markov model looks back at, after an empty unit marking the
+/// start of the phrase so that the first units are learnt too.
+///
+/// Between words and clauses only spaces and punctuation lie, which would
+/// hide the word before, so they are left out.
+fn context_of(units: &[String], granularity: Granularity) -> Vec<String> {
+    let start = std::iter::once(String::new());
+    let units = units.iter().cloned();
+    if granularity == Granularity::Letter {
+        start.chain(units).collect()
+    } else {
+        start
+            .chain(units.filter(|unit| is_inputable(unit)))
+            .collect()
+    }
+}
+
+fn add_count(counts: &mut Vec<(String, u32)>, letter: String) {
+    match counts.iter_mut().find(|(counted, _)| *counted == letter) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((letter, 1)),
+    }
+}
+
+fn weighted_pick(
+    counts: &[(String, u32)],
+    taken: &[String],
+    rng: &mut dyn RngCore,
+) -> Option<String> {
+    let free = counts
+        .iter()
+        .filter(|(letter, _)| !taken.contains(letter))
+        .collect::<Vec<_>>();
+    let total = free.iter().map(|(_, count)| count).sum::<u32>();
+    if total == 0 {
+        return None;
+    }
+    let mut pick = rng.gen_range(0..total);
+    for (letter, count) in free {
+        if pick < *count {
+            return Some(letter.clone());
+        }
+        pick -= count;
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn letters(line: &str) -> Vec<String> {
+        Granularity::Letter.split(line)
+    }
+
+    #[test]
+    fn uniform_avoids_taken_letters() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let taken = ('a'..='y').map(String::from).collect::<Vec<_>>();
+        for _ in 0..16 {
+            assert_eq!(Uniform.decoy(&[], &taken, &mut rng), "z");
+        }
+    }
+
+    #[test]
+    fn frequency_draws_from_the_phrases() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let frequency = Frequency::train(&["Жить, не тужить"], Granularity::Letter);
+        let alphabet = letters("житьнеу");
+        for _ in 0..64 {
+            let decoy = frequency.decoy(&[], &letters("ж"), &mut rng);
+            assert!(alphabet.contains(&decoy) && decoy != "ж", "{}", decoy);
+        }
+    }
+
+    #[test]
+    fn markov_continues_the_text() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let markov = Markov::train(&["abc", "abd", "xbe"], Granularity::Letter, 2);
+        for _ in 0..16 {
+            assert_eq!(markov.decoy(&letters("ab"), &letters("c"), &mut rng), "d");
+        }
+        // "xb" was only ever followed by "e", back off to anything after "b".
+        for _ in 0..16 {
+            let decoy = markov.decoy(&letters("xb"), &letters("e"), &mut rng);
+            assert!(decoy == "c" || decoy == "d", "{}", decoy);
+        }
+        // Phrases start with "a" or "x".
+        for _ in 0..16 {
+            let decoy = markov.decoy(&[], &letters("a"), &mut rng);
+            assert_eq!(decoy, "x");
+        }
+    }
+
+    #[test]
+    fn markov_continues_with_words() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let phrases = ["be healthy", "be wealthy", "find a good job"];
+        let markov = Markov::train(&phrases, Granularity::Word, 1);
+        let context = Granularity::Word.split("be ");
+        for _ in 0..16 {
+            let decoy = markov.decoy(&context, &["healthy".to_string()], &mut rng);
+            assert_eq!(decoy, "wealthy");
+        }
+    }
+
+    #[test]
+    fn makes_up_words_once_the_phrases_run_out() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let taken = ["be".to_string(), "healthy".to_string()];
+        let frequency = Frequency::train(&["be healthy"], Granularity::Word);
+        for _ in 0..16 {
+            let decoy = frequency.decoy(&[], &taken, &mut rng);
+            assert!(!taken.contains(&decoy), "{}", decoy);
+            assert!(decoy.len() >= 2, "{}", decoy);
+            assert!(
+                decoy.chars().all(|char| "behalty".contains(char)),
+                "{}",
+                decoy
+            );
+        }
+
+        let taken = ["be healthy".to_string()];
+        let markov = Markov::train(&["be healthy"], Granularity::Clause, 2);
+        for _ in 0..16 {
+            let decoy = markov.decoy(&[], &taken, &mut rng);
+            assert!(!taken.contains(&decoy), "{}", decoy);
+            assert!(decoy.split(' ').count() >= 2, "{}", decoy);
+        }
+    }
+
+    #[test]
+    fn markov_backs_off_to_any_letter() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let markov = Markov::train(&["ab"], Granularity::Letter, 2);
+        let decoy = markov.decoy(&letters("ab"), &letters("a b"), &mut rng);
+        assert!(!["a", "b", " "].contains(&decoy.as_str()));
+        assert_eq!(decoy.len(), 1);
+    }
+}
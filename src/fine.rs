@@ -1,4 +1,11 @@
-use super::data::DATA;
+use super::announce::Announcement;
+use super::controls::{spawn_action_button, Action, Bindings};
+use super::data::{PhrasePack, Phrases};
+use super::locale::Localization;
+use super::preferences::Preferences;
+use super::rng::GameRng;
+use super::theme::{FontRole, Palette, Theme};
+use super::tree::BranchTree;
 use super::{despawn_screen, GameState, GlobalData};
 use bevy::prelude::*;
 
@@ -21,57 +28,128 @@ fn fine_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     global_data: Res<GlobalData>,
+    phrases: Res<Phrases>,
+    packs: Res<Assets<PhrasePack>>,
+    localization: Res<Localization>,
+    rng: Res<GameRng>,
+    bindings: Res<Bindings>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+    mut announcements: EventWriter<Announcement>,
 ) {
-    let font = asset_server.load("fonts/FiraMono-Medium.ttf");
-
-    let fine_text = if global_data.fine_index < 7 {
-        format!("Wish you {}.", DATA[global_data.fine_index])
+    let style = |role: FontRole, size: f32| {
+        theme.text_style(
+            role,
+            size,
+            theme.palette.text,
+            &asset_server,
+            &localization,
+            &preferences,
+        )
+    };
+    let code = rng.code().to_string();
+    let mut footer = Vec::new();
+    if let Some(daily) = global_data.fine_daily {
+        let date = daily.date();
+        let steps = global_data.fine_steps.to_string();
+        let args = [("date", date.as_str()), ("steps", steps.as_str())];
+        footer.push(localization.format("fine-daily", &args));
+    }
+    let card = &global_data.fine_score;
+    let score = card.score().to_string();
+    let forks = card.forks.to_string();
+    let wrong_forks = card.wrong_forks.to_string();
+    let backtracks = card.backtracks.to_string();
+    let hints = card.hints.to_string();
+    let seconds = format!("{:.1}", card.seconds);
+    let key = if global_data.fine_best {
+        "fine-score-best"
     } else {
-        format!("{}.", DATA[global_data.fine_index])
+        "fine-score"
     };
+    footer.push(localization.format(key, &[("score", score.as_str())]));
+    footer.push(localization.format(
+        "fine-score-details",
+        &[
+            ("forks", forks.as_str()),
+            ("wrong", wrong_forks.as_str()),
+            ("backtracks", backtracks.as_str()),
+            ("hints", hints.as_str()),
+            ("seconds", seconds.as_str()),
+        ],
+    ));
+    // The daily is played by its date rather than its code.
+    if global_data.fine_daily.is_none() {
+        footer.push(localization.format("fine-code", &[("code", code.as_str())]));
+    }
+    let key = bindings.key_name(Action::Confirm);
+    footer.push(localization.format("fine-new-game", &[("key", key.as_str())]));
+
+    let pack = phrases.get(&packs).unwrap();
+    let fine_text = pack.reveal_text(
+        &pack.phrases[global_data.fine_index],
+        global_data.fine_steps,
+    );
+    announcements.send(Announcement(fine_text.clone()));
+    for line in footer.iter() {
+        announcements.send(Announcement(line.clone()));
+    }
 
     commands
-        .spawn_bundle(TextBundle {
+        .spawn_bundle(NodeBundle {
             style: Style {
-                margin: Rect {
-                    bottom: Val::Auto,
-                    left: Val::Px(50.0),
-                    right: Val::Px(50.0),
-                    top: Val::Auto,
-                },
+                margin: Rect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
                 ..Default::default()
             },
-            text: Text::with_section(
-                fine_text,
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 24.0,
-                    color: Color::WHITE,
-                },
-                Default::default(),
-            ),
+            color: theme.palette.background.into(),
             ..Default::default()
         })
-        .insert(OnFineScreen);
+        .insert(OnFineScreen)
+        .with_children(|parent| {
+            let font = asset_server.load(theme.font(FontRole::Choice, &localization));
+            spawn_tree(parent, &global_data.fine_tree, &font, &theme.palette);
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: Rect {
+                        left: Val::Px(theme.margins.text),
+                        right: Val::Px(theme.margins.text),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    fine_text,
+                    style(FontRole::Title, theme.sizes.title),
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            spawn_action_button(
+                parent,
+                style(FontRole::Body, theme.sizes.info),
+                &theme,
+                &localization,
+                "button-new-game",
+                Action::Confirm,
+            );
+        });
 
     commands
         .spawn_bundle(TextBundle {
             style: Style {
                 position: Rect {
-                    bottom: Val::Px(20.0),
-                    left: Val::Px(20.0),
+                    bottom: Val::Px(theme.margins.footer),
+                    left: Val::Px(theme.margins.footer),
                     ..Default::default()
                 },
                 position_type: PositionType::Absolute,
                 ..Default::default()
             },
             text: Text::with_section(
-                "Press Enter to start a new game",
-                TextStyle {
-                    font: font.clone(),
-                    font_size: 16.0,
-                    color: Color::WHITE,
-                },
+                footer.join("\n"),
+                style(FontRole::Body, theme.sizes.body),
                 Default::default(),
             ),
             ..Default::default()
@@ -79,8 +157,103 @@ fn fine_setup(
         .insert(OnFineScreen);
 }
 
-fn enter_game(mut game_state: ResMut<State<GameState>>, keyboard_input: Res<Input<KeyCode>>) {
-    if keyboard_input.just_released(KeyCode::Return) {
+/// Draws the forks of the run left to right, a row per branch and a column
+/// per fork. Each column holds the taken choice on top of the rejected ones.
+///
+/// Sized to fit the window rather than by the text size picked.
+fn spawn_tree(
+    parent: &mut ChildBuilder,
+    tree: &BranchTree,
+    font: &Handle<Font>,
+    palette: &Palette,
+) {
+    // Shrink the forks of long runs so that the deepest branch still fits.
+    let size = (380.0 / tree.depth().max(1) as f32).min(16.0);
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect {
+                    bottom: Val::Px(40.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            color: palette.background.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for branch in tree.branches() {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            margin: Rect {
+                                left: Val::Px(size * branch.depth as f32),
+                                top: Val::Px(size / 2.0),
+                                ..Default::default()
+                            },
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::FlexEnd,
+                            ..Default::default()
+                        },
+                        color: palette.background.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        for step in branch.steps.iter() {
+                            let color = match (branch.abandoned, step.is_lost()) {
+                                (false, false) => palette.choice,
+                                (false, true) => palette.alert,
+                                (true, false) => palette.dim,
+                                (true, true) => palette.dim_alert,
+                            };
+                            parent
+                                .spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(size), Val::Auto),
+                                        flex_direction: FlexDirection::ColumnReverse,
+                                        align_items: AlignItems::Center,
+                                        ..Default::default()
+                                    },
+                                    color: palette.background.into(),
+                                    ..Default::default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn_bundle(letter(&step.chosen, font, size, color));
+                                    for rejected in step.rejected() {
+                                        parent.spawn_bundle(letter(
+                                            rejected,
+                                            font,
+                                            size,
+                                            palette.faint,
+                                        ));
+                                    }
+                                });
+                        }
+                    });
+            }
+        });
+}
+
+fn letter(text: &str, font: &Handle<Font>, font_size: f32, color: Color) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            text,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color,
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+fn enter_game(mut game_state: ResMut<State<GameState>>, actions: Res<Input<Action>>) {
+    if actions.just_released(Action::Confirm) {
         game_state.set(GameState::Game).unwrap();
     }
 }
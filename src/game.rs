@@ -1,46 +1,77 @@
-use super::data::DATA;
-use super::utils::{is_inputable_char, to_lowercase};
-use super::{despawn_screen, GameState, GlobalData};
-use bevy::input::keyboard::KeyboardInput;
+use super::announce::Announcement;
+use super::codex::Codex;
+use super::controls::{spawn_corner_buttons, Action, Bindings};
+use super::daily::{Daily, DAILY_BRANCHES, DAILY_DECOYS, DAILY_GRANULARITY};
+use super::data::{PhrasePack, Phrases};
+use super::decoy::DecoyKind;
+use super::hint::{blanks, Hints};
+use super::locale::Localization;
+use super::path::{ForkingPath, Granularity, InfoType, InputMatch, Outcome, MIN_BRANCHES};
+use super::preferences::Preferences;
+use super::replay::{Replay, ReplayAction, ReplayStep, REPLAY_VERSION};
+use super::rng::{GameRng, RunCode};
+use super::score::ScoreCard;
+use super::theme::{FontRole, Theme};
+use super::timer::{Difficulty, ForkTimer};
+use super::tree::BranchTree;
+use super::{despawn_screen, is_portrait, GameState, GlobalData};
 use bevy::prelude::*;
 use rand::Rng;
-
-const CHAR_ENTRY: char = '?';
+use serde::{Deserialize, Serialize};
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Game>()
+            .init_resource::<GameConfig>()
+            .init_resource::<ForkTimer>()
             .add_system_set(SystemSet::on_enter(GameState::Game).with_system(game_setup))
             .add_system_set(
                 SystemSet::on_update(GameState::Game)
                     .with_system(handle_input.label("handle_input"))
+                    .with_system(
+                        tick_timer
+                            .label("tick_timer")
+                            .after("handle_input")
+                            .before("handle_choice"),
+                    )
                     .with_system(handle_choice.label("handle_choice").after("handle_input"))
-                    .with_system(display),
+                    .with_system(display)
+                    .with_system(display_focus.after("handle_input"))
+                    .with_system(display_timer)
+                    .with_system(layout_choices),
             )
             .add_system_set(
                 SystemSet::on_exit(GameState::Game)
                     .with_system(game_end)
                     .with_system(despawn_screen::<OnGameScreen>),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Replay).with_system(replay_setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::Replay)
+                    .with_system(play_replay.label("play_replay"))
+                    .with_system(display.after("play_replay"))
+                    .with_system(display_replay.after("play_replay"))
+                    .with_system(layout_choices),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Replay).with_system(despawn_screen::<OnGameScreen>),
             );
     }
 }
 
 #[derive(Component)]
-enum ButtonAction {
-    A,
-    B,
-}
+struct ChoiceButton(usize);
 
 #[derive(Component)]
-struct OnGameScreen;
+struct ChoiceRow;
 
 #[derive(Component)]
-struct TextChoiceA;
+struct OnGameScreen;
 
 #[derive(Component)]
-struct TextChoiceB;
+struct TextChoice(usize);
 
 #[derive(Component)]
 struct TextInfo;
@@ -48,66 +79,366 @@ struct TextInfo;
 #[derive(Component)]
 struct TextInput;
 
+#[derive(Component)]
+struct TextReplay;
+
+#[derive(Component)]
+struct TimerBar;
+
+/// How the next seekings are played.
+pub struct GameConfig {
+    /// How many choices every fork offers, outside of daily challenges.
+    pub branches: usize,
+    /// How the letters beside the true one are picked, outside of daily
+    /// challenges.
+    pub decoys: DecoyKind,
+    pub difficulty: Difficulty,
+    /// Whether forks offer letters, words or clauses.
+    pub granularity: Granularity,
+    pub mode: GameMode,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            branches: MIN_BRANCHES,
+            decoys: DecoyKind::default(),
+            difficulty: Difficulty::Untimed,
+            granularity: Granularity::Letter,
+            mode: GameMode::Random,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum GameMode {
+    /// A run from a fresh seed, or the run code asked for at launch.
+    Random,
+    /// The run shared by every player on the current UTC date.
+    Daily,
+}
+
 #[derive(Debug, Default)]
 struct Game {
-    active_choice_index: usize,
-    active_data_index: usize,
-    active_data_global_index: usize,
-    active_data_len: usize,
-    choices: [char; 2],
-    chosen_char: char,
-    datas: [Vec<char>; 2],
-    info_type: InfoType,
-    init_data0_index: usize,
-    init_data1_index: usize,
-    input_text: String,
-    is_first_choice: bool,
-    seeking_index: usize,
+    announced: Announced,
+    /// Set when the player asked to walk back a fork.
+    back: bool,
+    chosen: Option<String>,
+    daily: Option<Daily>,
+    /// The slot highlighted for players choosing with a gamepad or the arrow
+    /// keys, taken on confirm.
+    focus: usize,
+    hints: Hints,
+    path: ForkingPath,
+    /// Set once an input is pressed after the truth is revealed.
+    pressed_after_reveal: bool,
+    /// The run recorded so far, absent while a replay is watched.
+    replay: Option<Replay>,
+    score: ScoreCard,
+    /// When the run started, in seconds since startup.
+    started: f64,
+    tree: BranchTree,
+    /// Typed text that starts more than one choice so far.
+    typed: String,
 }
 
-#[derive(Debug, PartialEq)]
-enum InfoType {
-    None,
-    Fine,
-    Neverending,
-    Tedious,
+/// What was last announced to screen readers, so that only changes are.
+#[derive(Debug, Default)]
+struct Announced {
+    input: String,
+    info: String,
+    choices: Vec<String>,
 }
 
-impl Default for InfoType {
-    fn default() -> Self {
-        InfoType::None
+/// The replay being watched in `GameState::Replay`, and how far along it is.
+pub struct ReplayViewer {
+    replay: Option<Replay>,
+    /// Set when a replay was loaded and is waiting to be watched.
+    pending: bool,
+    /// Forks walked so far.
+    step: usize,
+    playing: bool,
+    /// Seconds of the run played back so far.
+    elapsed: f32,
+    /// Set once a fork differs from the recorded one, which means the replay
+    /// was recorded under other rules and plays back wrong from there.
+    desynced: bool,
+    /// Kept apart from `GameRng` so that watching leaves the next run alone.
+    rng: GameRng,
+}
+
+impl ReplayViewer {
+    pub fn new(requested: Option<Replay>) -> Self {
+        Self {
+            pending: requested.is_some(),
+            replay: requested,
+            step: 0,
+            playing: false,
+            elapsed: 0.0,
+            desynced: false,
+            rng: GameRng::new(None),
+        }
+    }
+
+    /// Queues `replay` to be watched from its start.
+    pub fn watch(&mut self, replay: Replay) {
+        self.replay = Some(replay);
+        self.pending = true;
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
+    fn total(&self) -> usize {
+        self.replay.as_ref().map_or(0, |replay| replay.steps.len())
+    }
+
+    /// Walks the run again from its seed up to its `step`th fork.
+    fn seek(&mut self, step: usize, path: &mut ForkingPath) {
+        let replay = match &self.replay {
+            Some(replay) => replay,
+            None => return,
+        };
+        let texts = replay
+            .phrases
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        self.rng.start_code(RunCode {
+            seed: replay.seed,
+            branches: replay.branches,
+            granularity: replay.granularity,
+            decoys: replay.decoys,
+        });
+        *path = start_path(&texts, replay.mode, self.rng.code(), &mut self.rng);
+        self.step = 0;
+        self.elapsed = 0.0;
+        self.desynced = false;
+        while self.step < step && self.advance(path) {}
+    }
+
+    /// Plays the next recorded action, if any is left.
+    fn advance(&mut self, path: &mut ForkingPath) -> bool {
+        let recorded = match self
+            .replay
+            .as_ref()
+            .and_then(|replay| replay.steps.get(self.step))
+        {
+            Some(recorded) => recorded,
+            None => return false,
+        };
+        if path.choices() != recorded.choices.as_slice() {
+            self.desynced = true;
+        }
+        match &recorded.action {
+            ReplayAction::Choose(choice) => {
+                path.choose(choice, &mut self.rng);
+            }
+            ReplayAction::Back => {
+                path.back();
+            }
+        }
+        self.elapsed = recorded.time;
+        self.step += 1;
+        true
+    }
+
+    /// When the next recorded action was taken.
+    fn next_time(&self) -> Option<f32> {
+        let replay = self.replay.as_ref()?;
+        Some(replay.steps.get(self.step)?.time)
     }
 }
 
-fn game_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMut<Game>) {
-    let font = asset_server.load("fonts/FiraMono-Medium.ttf");
-
-    let mut rng = rand::thread_rng();
-    let data_count = DATA.len();
-    let rand0 = rng.gen_range(0..data_count);
-    game.datas[0] = String::from(DATA[rand0]).chars().collect();
-    loop {
-        let rand1 = rng.gen_range(0..data_count);
-        if rand1 != rand0 {
-            game.datas[1] = String::from(DATA[rand1]).chars().collect();
-            game.init_data0_index = rand0;
-            game.init_data1_index = rand1;
-            break;
+/// Starts the path of the run of `code` from an `rng` already seeded for it.
+fn start_path(texts: &[&str], mode: GameMode, code: RunCode, rng: &mut GameRng) -> ForkingPath {
+    let RunCode {
+        branches,
+        granularity,
+        decoys,
+        ..
+    } = code;
+    let decoys = decoys.build(texts, granularity);
+    match mode {
+        GameMode::Random => ForkingPath::new(texts, branches, granularity, decoys, rng),
+        GameMode::Daily => {
+            let targets = ForkingPath::revealable(texts, granularity);
+            let target = targets[rng.gen_range(0..targets.len())];
+            ForkingPath::toward(texts, target, branches, granularity, decoys, rng)
         }
     }
+}
 
-    game.choices[0] = to_lowercase(game.datas[0][0]);
-    game.choices[1] = to_lowercase(game.datas[1][0]);
+fn game_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut game: ResMut<Game>,
+    phrases: Res<Phrases>,
+    packs: Res<Assets<PhrasePack>>,
+    localization: Res<Localization>,
+    config: Res<GameConfig>,
+    mut rng: ResMut<GameRng>,
+    mut timer: ResMut<ForkTimer>,
+    time: Res<Time>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+) {
+    let pack = phrases.get(&packs).unwrap();
+    let texts = pack
+        .phrases
+        .iter()
+        .map(|phrase| phrase.text.as_str())
+        .collect::<Vec<_>>();
+    let code = match config.mode {
+        GameMode::Random => {
+            game.daily = None;
+            rng.start_run(config.branches, config.granularity, config.decoys)
+        }
+        GameMode::Daily => {
+            let daily = Daily::today();
+            let code = RunCode {
+                seed: daily.seed(),
+                branches: DAILY_BRANCHES,
+                granularity: DAILY_GRANULARITY,
+                decoys: DAILY_DECOYS,
+            };
+            rng.start_code(code);
+            game.daily = Some(daily);
+            code
+        }
+    };
+    game.path = start_path(&texts, config.mode, code, &mut rng);
+    game.back = false;
+    game.chosen = None;
+    game.replay = Some(Replay {
+        version: REPLAY_VERSION,
+        seed: code.seed,
+        mode: config.mode,
+        branches: code.branches,
+        granularity: code.granularity,
+        decoys: code.decoys,
+        pack: pack.name.clone(),
+        pack_version: pack.version,
+        phrases: texts.iter().map(|text| text.to_string()).collect(),
+        steps: Vec::new(),
+    });
+    game.announced = Announced::default();
+    game.focus = 0;
+    game.hints = Hints::default();
+    game.pressed_after_reveal = false;
+    game.started = time.seconds_since_startup();
+    game.score = ScoreCard::default();
+    game.tree = BranchTree::default();
+    game.typed.clear();
+    timer.start(config.difficulty.time_limit(game.path.seeking_index()));
+
+    spawn_game_screen(
+        &mut commands,
+        &game.path,
+        &theme,
+        &asset_server,
+        &localization,
+        &preferences,
+    );
+}
 
-    game.active_choice_index = 0;
-    game.active_data_index = 0;
-    game.active_data_global_index = 0;
-    game.active_data_len = 0;
-    game.chosen_char = CHAR_ENTRY;
-    game.info_type = InfoType::None;
-    game.input_text.clear();
-    game.is_first_choice = true;
-    game.seeking_index = 0;
+fn replay_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut game: ResMut<Game>,
+    mut viewer: ResMut<ReplayViewer>,
+    localization: Res<Localization>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+) {
+    viewer.pending = false;
+    viewer.playing = false;
+    viewer.seek(0, &mut game.path);
+    game.announced = Announced::default();
+    game.back = false;
+    game.chosen = None;
+    game.daily = None;
+    game.focus = 0;
+    game.hints = Hints::default();
+    game.pressed_after_reveal = false;
+    game.replay = None;
+    game.typed.clear();
+
+    spawn_game_screen(
+        &mut commands,
+        &game.path,
+        &theme,
+        &asset_server,
+        &localization,
+        &preferences,
+    );
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position: Rect {
+                    bottom: Val::Px(theme.margins.footer),
+                    left: Val::Px(theme.margins.footer),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                theme.text_style(
+                    FontRole::Body,
+                    theme.sizes.body,
+                    theme.palette.text,
+                    &asset_server,
+                    &localization,
+                    &preferences,
+                ),
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(OnGameScreen)
+        .insert(TextReplay);
+
+    let style = theme.text_style(
+        FontRole::Body,
+        theme.sizes.info,
+        theme.palette.text,
+        &asset_server,
+        &localization,
+        &preferences,
+    );
+    let buttons = spawn_corner_buttons(
+        &mut commands,
+        style,
+        &theme,
+        &localization,
+        &[
+            ("button-play", Action::PlayPause),
+            ("button-back", Action::Back),
+        ],
+    );
+    commands.entity(buttons).insert(OnGameScreen);
+}
+
+/// The screen shared by runs and replays: the info text, a row of choices
+/// and the text put together so far.
+///
+/// Clauses are too wide to sit side by side, so they are stacked instead.
+fn spawn_game_screen(
+    commands: &mut Commands,
+    path: &ForkingPath,
+    theme: &Theme,
+    asset_server: &AssetServer,
+    localization: &Localization,
+    preferences: &Preferences,
+) {
+    let palette = &theme.palette;
+    let style = |role: FontRole, size: f32, color: Color| {
+        theme.text_style(role, size, color, asset_server, localization, preferences)
+    };
 
     commands
         .spawn_bundle(NodeBundle {
@@ -117,7 +448,7 @@ fn game_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game:
                 align_items: AlignItems::Center,
                 ..Default::default()
             },
-            color: Color::BLACK.into(),
+            color: palette.background.into(),
             ..Default::default()
         })
         .insert(OnGameScreen)
@@ -125,16 +456,12 @@ fn game_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game:
             parent
                 .spawn_bundle(TextBundle {
                     style: Style {
-                        margin: Rect::all(Val::Px(50.0)),
+                        margin: Rect::all(Val::Px(theme.margins.text)),
                         ..Default::default()
                     },
                     text: Text::with_section(
                         "",
-                        TextStyle {
-                            font: font.clone(),
-                            font_size: 20.0,
-                            color: Color::WHITE,
-                        },
+                        style(FontRole::Body, theme.sizes.info, palette.text),
                         Default::default(),
                     ),
                     ..Default::default()
@@ -144,82 +471,108 @@ fn game_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game:
                 .spawn_bundle(NodeBundle {
                     style: Style {
                         margin: Rect::all(Val::Auto),
-                        flex_direction: FlexDirection::Row,
+                        flex_direction: choice_direction(path.granularity(), false),
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
                         align_items: AlignItems::Center,
                         ..Default::default()
                     },
-                    color: Color::BLACK.into(),
+                    color: palette.background.into(),
                     ..Default::default()
                 })
+                .insert(ChoiceRow)
                 .with_children(|parent| {
-                    parent
-                        .spawn_bundle(ButtonBundle {
-                            style: Style {
-                                margin: Rect::all(Val::Px(50.0)),
+                    // Narrow the row as it grows so that it still fits the window.
+                    let branches = path.branches();
+                    let sizes = &theme.sizes;
+                    let (margin, font_size) = match path.granularity() {
+                        Granularity::Letter if branches > 4 => {
+                            (100.0 / branches as f32, sizes.letter * 0.75)
+                        }
+                        Granularity::Letter => (100.0 / branches as f32, sizes.letter),
+                        Granularity::Word if branches > 4 => {
+                            (40.0 / branches as f32, sizes.word * 0.7)
+                        }
+                        Granularity::Word => (40.0 / branches as f32, sizes.word),
+                        Granularity::Clause => (8.0, sizes.clause),
+                    };
+                    for slot in 0..branches {
+                        parent
+                            .spawn_bundle(ButtonBundle {
+                                style: Style {
+                                    // Big enough for a finger.
+                                    min_size: Size::new(Val::Px(48.0), Val::Px(48.0)),
+                                    margin: Rect::all(Val::Px(margin)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..Default::default()
+                                },
+                                color: palette.background.into(),
                                 ..Default::default()
-                            },
-                            color: Color::BLACK.into(),
+                            })
+                            .insert(ChoiceButton(slot))
+                            .with_children(|parent| {
+                                parent
+                                    .spawn_bundle(TextBundle {
+                                        text: Text::with_section(
+                                            "",
+                                            style(FontRole::Choice, font_size, palette.choice),
+                                            Default::default(),
+                                        ),
+                                        ..Default::default()
+                                    })
+                                    .insert(TextChoice(slot));
+                            });
+                    }
+                });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(200.0), Val::Px(6.0)),
+                        margin: Rect {
+                            top: Val::Px(20.0),
                             ..Default::default()
-                        })
-                        .insert(ButtonAction::A)
-                        .with_children(|parent| {
-                            parent
-                                .spawn_bundle(TextBundle {
-                                    text: Text::with_section(
-                                        game.choices[0],
-                                        TextStyle {
-                                            font: font.clone(),
-                                            font_size: 80.0,
-                                            color: Color::YELLOW,
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                })
-                                .insert(TextChoiceA);
-                        });
+                        },
+                        ..Default::default()
+                    },
+                    color: palette.background.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    // Left empty outside of timed runs.
                     parent
-                        .spawn_bundle(ButtonBundle {
+                        .spawn_bundle(NodeBundle {
                             style: Style {
-                                margin: Rect::all(Val::Px(50.0)),
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
                                 ..Default::default()
                             },
-                            color: Color::BLACK.into(),
+                            color: palette.choice.into(),
                             ..Default::default()
                         })
-                        .insert(ButtonAction::B)
-                        .with_children(|parent| {
-                            parent
-                                .spawn_bundle(TextBundle {
-                                    text: Text::with_section(
-                                        game.choices[1],
-                                        TextStyle {
-                                            font: font.clone(),
-                                            font_size: 80.0,
-                                            color: Color::YELLOW,
-                                        },
-                                        Default::default(),
-                                    ),
-                                    ..Default::default()
-                                })
-                                .insert(TextChoiceB);
-                        });
+                        .insert(TimerBar);
                 });
             parent
                 .spawn_bundle(TextBundle {
                     style: Style {
-                        margin: Rect::all(Val::Px(50.0)),
+                        margin: Rect::all(Val::Px(theme.margins.text)),
                         ..Default::default()
                     },
-                    text: Text::with_section(
-                        game.input_text.clone(),
-                        TextStyle {
-                            font: font.clone(),
-                            font_size: 20.0,
-                            color: Color::WHITE,
-                        },
-                        Default::default(),
-                    ),
+                    // The text walked so far, then what hints reveal of the
+                    // rest: the next word and blanks for the letters after.
+                    text: Text {
+                        sections: [
+                            (FontRole::Body, palette.text),
+                            (FontRole::Hint, palette.dim),
+                            (FontRole::Hint, palette.faint),
+                        ]
+                        .into_iter()
+                        .map(|(role, color)| TextSection {
+                            value: String::new(),
+                            style: style(role, theme.sizes.info, color),
+                        })
+                        .collect(),
+                        alignment: Default::default(),
+                    },
                     ..Default::default()
                 })
                 .insert(TextInput);
@@ -227,200 +580,492 @@ fn game_setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game:
 }
 
 fn display(
-    game: Res<Game>,
+    mut game: ResMut<Game>,
+    localization: Res<Localization>,
+    bindings: Res<Bindings>,
+    theme: Res<Theme>,
+    preferences: Res<Preferences>,
+    time: Res<Time>,
+    mut announcements: EventWriter<Announcement>,
     mut query: QuerySet<(
-        QueryState<&mut Text, With<TextChoiceA>>,
-        QueryState<&mut Text, With<TextChoiceB>>,
+        QueryState<(&mut Text, &TextChoice)>,
         QueryState<&mut Text, With<TextInfo>>,
         QueryState<&mut Text, With<TextInput>>,
     )>,
 ) {
-    for mut text in query.q0().iter_mut() {
-        text.sections[0].value = format!("{}", game.choices[0]);
+    let palette = &theme.palette;
+    let flashed = game.hints.flashed(time.seconds_since_startup());
+    for (mut text, slot) in query.q0().iter_mut() {
+        let choice = game.path.choices().get(slot.0).cloned().unwrap_or_default();
+        let is_flashed = flashed == Some(choice.as_str());
+        text.sections[0].style.color = if is_flashed {
+            palette.flash
+        } else {
+            palette.choice
+        };
+        text.sections[0].value = if preferences.accessible {
+            choice_label(&choice, slot.0, slot.0 == game.focus, is_flashed)
+        } else {
+            choice
+        };
+    }
+
+    let key = match (game.path.info_type(), game.path.granularity()) {
+        (InfoType::Fine, _) => "game-fine",
+        (InfoType::Tedious, _) => "game-tedious",
+        (_, Granularity::Letter) => "game-seeking",
+        (_, Granularity::Word) => "game-seeking-word",
+        (_, Granularity::Clause) => "game-seeking-clause",
+    };
+    let undo = bindings.key_name(Action::Undo);
+    let back = bindings.key_name(Action::Back);
+    let mut info = localization.format(key, &[("undo", undo.as_str()), ("back", back.as_str())]);
+    // Hints are only offered while playing, not while watching.
+    if let (Some(_), Some(hint)) = (&game.replay, game.hints.next()) {
+        if game.path.hint_choice().is_some() {
+            let key = bindings.key_name(Action::Hint);
+            let cost = hint.cost().to_string();
+            info.push('\n');
+            info.push_str(&localization.format(
+                "game-hint",
+                &[("key", key.as_str()), ("cost", cost.as_str())],
+            ));
+        }
     }
     for mut text in query.q1().iter_mut() {
-        text.sections[0].value = format!("{}", game.choices[1]);
+        text.sections[0].value = info.clone();
     }
 
+    let input = game.path.input_text();
+    let rest = game.path.rest().unwrap_or_default();
+    let word = game.hints.revealed_word(input).unwrap_or_default();
+    let after = rest.strip_prefix(word).unwrap_or_default();
     for mut text in query.q2().iter_mut() {
-        text.sections[0].value = format!(
-            "{}",
-            match game.info_type {
-                InfoType::Fine => "Great. You have revealed the truth.\nPress any key to continue.",
-                InfoType::Tedious =>
-                    "Wait...\nDo you think you've pressed\ntoo many letters?\nTry press Esc...",
-                _ => "Press one of the following letter\nthen you might find the truth.",
-            }
-        );
+        text.sections[0].value = input.to_string();
+        text.sections[1].value = word.to_string();
+        text.sections[2].value = if game.hints.shows_blanks() {
+            blanks(after)
+        } else {
+            String::new()
+        };
     }
 
-    for mut text in query.q3().iter_mut() {
-        text.sections[0].value = format!("{}", game.input_text);
+    // Screen readers are told what changed: the text typed, the info text,
+    // then the choices. Compared first, so that `Game` is only marked as
+    // changed when something is announced.
+    let input = input.to_string();
+    if game.announced.input != input {
+        announcements.send(Announcement(input.clone()));
+        game.announced.input = input;
+    }
+    if game.announced.info != info {
+        announcements.send(Announcement(info.clone()));
+        game.announced.info = info;
+    }
+    if game.announced.choices != game.path.choices() {
+        let choices = game.path.choices().to_vec();
+        announcements.send(Announcement(announce_choices(&choices, &localization)));
+        game.announced.choices = choices;
     }
 }
 
-fn handle_choice(mut game: ResMut<Game>) {
-    if game.info_type == InfoType::Fine {
-        return;
+/// The choices as read out, e.g. "slot 1: e, slot 2: q".
+fn announce_choices(choices: &[String], localization: &Localization) -> String {
+    choices
+        .iter()
+        .enumerate()
+        .map(|(slot, choice)| {
+            let slot = (slot + 1).to_string();
+            localization.format(
+                "announce-choice",
+                &[("slot", slot.as_str()), ("choice", choice.as_str())],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A choice in accessibility mode: numbered after its slot, and marked when
+/// focused or flashed by a hint, so that colors are never the only cue.
+fn choice_label(choice: &str, slot: usize, focused: bool, flashed: bool) -> String {
+    if choice.is_empty() {
+        return String::new();
     }
+    let focus = if focused { ">" } else { "" };
+    let flash = if flashed { " *" } else { "" };
+    format!("{}[{}] {}{}", focus, slot + 1, choice, flash)
+}
 
-    let c = game.chosen_char;
-    if c != CHAR_ENTRY {
-        if c == game.choices[0] || c == game.choices[1] {
-            game.input_text.push(c);
-
-            if game.is_first_choice {
-                game.active_choice_index = if c == game.choices[0] { 0 } else { 1 };
-                game.active_data_index = game.active_choice_index;
-                game.active_data_global_index = if game.active_choice_index == 0 {
-                    game.init_data0_index
-                } else {
-                    game.init_data1_index
-                };
-                game.active_data_len = game.datas[game.active_data_index].len();
-                game.is_first_choice = false;
-            }
+/// Lays the choices out in a column where a row would not fit: clauses, and
+/// words on portrait screens. Rows of letters wrap instead.
+fn choice_direction(granularity: Granularity, portrait: bool) -> FlexDirection {
+    match (granularity, portrait) {
+        (Granularity::Clause, _) | (Granularity::Word, true) => FlexDirection::ColumnReverse,
+        _ => FlexDirection::Row,
+    }
+}
 
-            if c != game.choices[game.active_choice_index] {
-                game.info_type = InfoType::Neverending;
-            }
-            if game.seeking_index == game.active_data_len - 1 {
-                if game.info_type != InfoType::Neverending {
-                    game.info_type = InfoType::Fine;
-                }
-            }
-            if game.info_type == InfoType::Neverending {
-                if game.seeking_index >= 10 {
-                    game.info_type = InfoType::Tedious;
-                }
-            }
+/// Follows the window as it turns between landscape and portrait.
+fn layout_choices(
+    game: Res<Game>,
+    windows: Res<Windows>,
+    mut query: Query<&mut Style, With<ChoiceRow>>,
+) {
+    let direction = choice_direction(game.path.granularity(), is_portrait(&windows));
+    for mut style in query.iter_mut() {
+        // Only written on a change, so that the layout is not redone every
+        // frame.
+        if style.flex_direction != direction {
+            style.flex_direction = direction;
+        }
+    }
+}
 
-            if game.info_type == InfoType::Fine {
-                return;
-            }
+/// Highlights the focused choice, which stays lit while the mouse is away.
+fn display_focus(
+    game: Res<Game>,
+    theme: Res<Theme>,
+    mut query: Query<(&mut UiColor, &ChoiceButton)>,
+) {
+    let palette = &theme.palette;
+    for (mut color, button) in query.iter_mut() {
+        *color = if button.0 == game.focus {
+            palette.focus.into()
+        } else {
+            palette.background.into()
+        };
+    }
+}
 
-            game.seeking_index += 1;
-
-            let mut rng = rand::thread_rng();
-            let rand_letter = rng.gen_range(b'a'..=b'z') as char;
-            let rand0 = rng.gen_range(0..=1);
-            let rand1 = 1 - rand0;
-            game.choices[rand0] = rand_letter;
-            if game.info_type != InfoType::None {
-                game.choices[rand1] = rng.gen_range(b'a'..=b'z') as char;
-            } else {
-                let mut c = game.datas[game.active_data_index][game.seeking_index];
-                loop {
-                    if !is_inputable_char(c) {
-                        game.input_text.push(c);
-                        game.seeking_index += 1;
-                        c = game.datas[game.active_data_index][game.seeking_index];
-                    } else {
-                        break;
-                    }
-                }
-                game.choices[rand1] = to_lowercase(c);
-                game.active_choice_index = rand1;
-            }
+fn display_replay(
+    viewer: Res<ReplayViewer>,
+    localization: Res<Localization>,
+    bindings: Res<Bindings>,
+    mut query: Query<&mut Text, With<TextReplay>>,
+) {
+    let step = viewer.step.to_string();
+    let total = viewer.total().to_string();
+    let mut lines = vec![localization.format(
+        "replay-step",
+        &[("step", step.as_str()), ("total", total.as_str())],
+    )];
+    if viewer.desynced {
+        lines.push(localization.get("replay-desynced").to_string());
+    }
+    let left = bindings.key_name(Action::Left);
+    let right = bindings.key_name(Action::Right);
+    let play = bindings.key_name(Action::PlayPause);
+    let back = bindings.key_name(Action::Back);
+    lines.push(localization.format(
+        "replay-controls",
+        &[
+            ("left", left.as_str()),
+            ("right", right.as_str()),
+            ("play", play.as_str()),
+            ("back", back.as_str()),
+        ],
+    ));
+
+    for mut text in query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn display_timer(
+    timer: Res<ForkTimer>,
+    theme: Res<Theme>,
+    mut query: Query<(&mut Style, &mut UiColor), With<TimerBar>>,
+) {
+    let palette = &theme.palette;
+    let fraction = timer.fraction();
+    for (mut style, mut color) in query.iter_mut() {
+        style.size.width = Val::Percent(fraction * 100.0);
+        *color = if fraction < 0.3 {
+            palette.alert.into()
+        } else {
+            palette.choice.into()
+        };
+    }
+}
+
+/// Takes a random choice for the player once the time of a fork runs out.
+fn tick_timer(mut game: ResMut<Game>, mut timer: ResMut<ForkTimer>, time: Res<Time>) {
+    if !timer.tick(time.delta_seconds()) {
+        return;
+    }
+    if game.path.info_type() == InfoType::Fine || game.back || game.chosen.is_some() {
+        return;
+    }
+
+    // Not drawn from `GameRng`, so that replays walk the same forks whatever
+    // was picked here.
+    let choices = game.path.choices();
+    let choice = choices[rand::thread_rng().gen_range(0..choices.len())].clone();
+    game.chosen = Some(choice);
+    game.typed.clear();
+}
+
+fn handle_choice(
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    mut timer: ResMut<ForkTimer>,
+    config: Res<GameConfig>,
+    time: Res<Time>,
+) {
+    let game = &mut *game;
+    let choices = game.path.choices().to_vec();
+    let action = if std::mem::take(&mut game.back) {
+        if !game.path.back() {
+            return;
+        }
+        game.score.record_back();
+        game.tree.back();
+        game.hints.next_fork();
+        ReplayAction::Back
+    } else if let Some(choice) = game.chosen.take() {
+        let before = game.path.info_type();
+        let info_type = match game.path.choose(&choice, &mut *rng) {
+            Outcome::Chosen(info_type) => info_type,
+            Outcome::Ignored => return,
+        };
+        game.score.record_choice(before, info_type);
+        if info_type == InfoType::Fine {
+            let seconds = (time.seconds_since_startup() - game.started) as f32;
+            game.score.finish(game.path.forks().unwrap(), seconds);
         }
+        game.tree.choose(&choices, &choice, info_type);
+        game.hints.next_fork();
+        ReplayAction::Choose(choice)
+    } else {
+        return;
+    };
+
+    // Every fork gets a fresh countdown, none once the truth is revealed.
+    if game.path.info_type() == InfoType::Fine {
+        timer.stop();
+    } else {
+        timer.start(config.difficulty.time_limit(game.path.seeking_index()));
+    }
+
+    if let Some(replay) = &mut game.replay {
+        replay.steps.push(ReplayStep {
+            choices,
+            action,
+            time: (time.seconds_since_startup() - game.started) as f32,
+        });
+    }
+}
+
+fn play_replay(
+    mut game: ResMut<Game>,
+    mut viewer: ResMut<ReplayViewer>,
+    mut game_state: ResMut<State<GameState>>,
+    actions: Res<Input<Action>>,
+    time: Res<Time>,
+) {
+    if actions.just_released(Action::Back) {
+        game_state.set(GameState::Splash).unwrap();
+        return;
     }
 
-    game.chosen_char = CHAR_ENTRY;
+    if actions.just_released(Action::Right) {
+        viewer.playing = false;
+        viewer.advance(&mut game.path);
+    }
+    if actions.just_released(Action::Left) && viewer.step > 0 {
+        viewer.playing = false;
+        let step = viewer.step - 1;
+        viewer.seek(step, &mut game.path);
+    }
+    if actions.just_released(Action::PlayPause) {
+        viewer.playing = !viewer.playing;
+    }
+
+    if viewer.playing {
+        // Choices are played back at the pace they were made.
+        viewer.elapsed += time.delta_seconds();
+        while viewer
+            .next_time()
+            .map_or(false, |next| next <= viewer.elapsed)
+        {
+            viewer.advance(&mut game.path);
+        }
+        if viewer.step == viewer.total() {
+            viewer.playing = false;
+        }
+    }
 }
 
 fn handle_input(
     mut game: ResMut<Game>,
     mut game_state: ResMut<State<GameState>>,
+    actions: Res<Input<Action>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    time: Res<Time>,
     keys: Res<Input<KeyCode>>,
-    mut key_events: EventReader<KeyboardInput>,
-    interaction_query: Query<(&Interaction, &ButtonAction), (Changed<Interaction>, With<Button>)>,
-    mut query: QuerySet<(
-        QueryState<&Text, With<TextChoiceA>>,
-        QueryState<&Text, With<TextChoiceB>>,
-    )>,
+    mut char_events: EventReader<ReceivedCharacter>,
+    interaction_query: Query<(&Interaction, &ChoiceButton), (Changed<Interaction>, With<Button>)>,
 ) {
-    if game.info_type == InfoType::Fine {
-        use bevy::input::ElementState;
-        for e in key_events.iter() {
-            match e.state {
-                ElementState::Released => {
-                    game_state.set(GameState::Fine).unwrap();
-                    return;
-                }
-                _ => {}
-            }
+    if game.path.info_type() == InfoType::Fine {
+        // Any key, bound gamepad button, tap or click goes on once let go,
+        // but only if pressed after the reveal: the key that completed the
+        // phrase may still be held.
+        if keys.get_just_pressed().next().is_some()
+            || actions.get_just_pressed().next().is_some()
+            || mouse_buttons.get_just_pressed().next().is_some()
+            || touches.iter_just_pressed().next().is_some()
+        {
+            game.pressed_after_reveal = true;
         }
+        let released = keys.get_just_released().next().is_some()
+            || actions.get_just_released().next().is_some()
+            || mouse_buttons.get_just_released().next().is_some()
+            || touches.iter_just_released().next().is_some();
+        if game.pressed_after_reveal && released {
+            game_state.set(GameState::Fine).unwrap();
+            return;
+        }
+    } else if game.pressed_after_reveal {
+        // Walked back from the reveal.
+        game.pressed_after_reveal = false;
     }
 
-    if keys.just_released(KeyCode::Escape) {
+    if actions.just_released(Action::Back) {
         game_state.set(GameState::Splash).unwrap();
         return;
     }
 
-    let keys = keys.get_just_released();
-    for key in keys.into_iter() {
-        match key {
-            KeyCode::A => game.chosen_char = 'a',
-            KeyCode::B => game.chosen_char = 'b',
-            KeyCode::C => game.chosen_char = 'c',
-            KeyCode::D => game.chosen_char = 'd',
-            KeyCode::E => game.chosen_char = 'e',
-            KeyCode::F => game.chosen_char = 'f',
-            KeyCode::G => game.chosen_char = 'g',
-            KeyCode::H => game.chosen_char = 'h',
-            KeyCode::I => game.chosen_char = 'i',
-            KeyCode::J => game.chosen_char = 'j',
-            KeyCode::K => game.chosen_char = 'k',
-            KeyCode::L => game.chosen_char = 'l',
-            KeyCode::M => game.chosen_char = 'm',
-            KeyCode::N => game.chosen_char = 'n',
-            KeyCode::O => game.chosen_char = 'o',
-            KeyCode::P => game.chosen_char = 'p',
-            KeyCode::Q => game.chosen_char = 'q',
-            KeyCode::R => game.chosen_char = 'r',
-            KeyCode::S => game.chosen_char = 's',
-            KeyCode::T => game.chosen_char = 't',
-            KeyCode::U => game.chosen_char = 'u',
-            KeyCode::V => game.chosen_char = 'v',
-            KeyCode::W => game.chosen_char = 'w',
-            KeyCode::X => game.chosen_char = 'x',
-            KeyCode::Y => game.chosen_char = 'y',
-            KeyCode::Z => game.chosen_char = 'z',
-            KeyCode::Key0 => game.chosen_char = '0',
-            KeyCode::Key1 => game.chosen_char = '1',
-            KeyCode::Key2 => game.chosen_char = '2',
-            KeyCode::Key3 => game.chosen_char = '3',
-            KeyCode::Key4 => game.chosen_char = '4',
-            KeyCode::Key5 => game.chosen_char = '5',
-            KeyCode::Key6 => game.chosen_char = '6',
-            KeyCode::Key7 => game.chosen_char = '7',
-            KeyCode::Key8 => game.chosen_char = '8',
-            KeyCode::Key9 => game.chosen_char = '9',
-            _ => {}
+    if actions.just_pressed(Action::Undo) {
+        // Half-typed text is dropped first, then the path is walked back.
+        if game.typed.is_empty() {
+            game.back = true;
+        } else {
+            game.typed.clear();
         }
     }
 
-    for (interaction, button_action) in interaction_query.iter() {
-        match *interaction {
-            Interaction::Clicked => match button_action {
-                ButtonAction::A => {
-                    for text in query.q0().iter() {
-                        game.chosen_char = text.sections[0].value.chars().collect::<Vec<_>>()[0];
-                    }
-                }
-                ButtonAction::B => {
-                    for text in query.q1().iter() {
-                        game.chosen_char = text.sections[0].value.chars().collect::<Vec<_>>()[0];
-                    }
+    if actions.just_pressed(Action::Hint) {
+        take_hint(&mut game, time.seconds_since_startup());
+    }
+
+    let typed = char_events
+        .iter()
+        .map(|event| event.char)
+        .filter(|char| !char.is_control())
+        .collect::<Vec<_>>();
+    // Keys bound to slots, such as the number keys, pick by position rather
+    // than typing what they print, unless that continues one of the choices,
+    // so that phrases with digits can still be typed.
+    let continues = |char: char| {
+        let mut text = game.typed.clone();
+        text.push(char);
+        game.path.match_input(&text) != InputMatch::None
+            || game.path.match_input(&char.to_string()) != InputMatch::None
+    };
+    let slot = actions
+        .get_just_pressed()
+        .find_map(|action| match action {
+            Action::ChooseSlot(slot) => Some(*slot),
+            _ => None,
+        })
+        .filter(|_| !typed.iter().any(|&char| continues(char)));
+    if let Some(slot) = slot {
+        if let Some(choice) = game.path.choices().get(slot).cloned() {
+            game.chosen = Some(choice);
+            game.typed.clear();
+        }
+    }
+
+    let slots = game.path.choices().len().max(1);
+    if actions.just_pressed(Action::Left) {
+        game.focus = (game.focus + slots - 1) % slots;
+    }
+    if actions.just_pressed(Action::Right) {
+        game.focus = (game.focus + 1) % slots;
+    }
+    // Swiping up takes the focused choice on touch screens.
+    if actions.just_pressed(Action::Confirm) || actions.just_pressed(Action::Up) {
+        if let Some(choice) = game.path.choices().get(game.focus).cloned() {
+            game.chosen = Some(choice);
+            game.typed.clear();
+        }
+    }
+
+    for char in typed {
+        if slot.is_some() {
+            continue;
+        }
+
+        game.typed.push(char);
+        if game.path.match_input(&game.typed) == InputMatch::None {
+            game.typed = char.to_string();
+        }
+        match game.path.match_input(&game.typed) {
+            InputMatch::Choice(choice) => {
+                game.chosen = Some(choice);
+                game.typed.clear();
+            }
+            InputMatch::Partial => {}
+            InputMatch::None => game.typed.clear(),
+        }
+    }
+
+    for (interaction, button) in interaction_query.iter() {
+        match interaction {
+            Interaction::Clicked => {
+                let choice = game.path.choices().get(button.0).cloned();
+                if choice.is_some() {
+                    game.chosen = choice;
                 }
-            },
-            _ => {}
+            }
+            // The mouse moves the focus too, so that only one choice is ever
+            // highlighted.
+            Interaction::Hovered => game.focus = button.0,
+            Interaction::None => {}
         }
     }
 }
 
-fn game_end(mut global_data: ResMut<GlobalData>, game: Res<Game>) {
-    if game.info_type == InfoType::Fine {
-        global_data.fine_index = game.active_data_global_index;
+/// Gives the next hint at the current fork, at a cost to the score.
+fn take_hint(game: &mut Game, now: f64) {
+    let (choice, rest) = match (game.path.hint_choice(), game.path.rest()) {
+        (Some(choice), Some(rest)) => (choice, rest),
+        _ => return,
+    };
+    if let Some(hint) = game.hints.take(choice, game.path.input_text(), &rest, now) {
+        game.score.record_hint(hint);
+    }
+}
+
+fn game_end(
+    mut global_data: ResMut<GlobalData>,
+    mut game: ResMut<Game>,
+    mut codex: ResMut<Codex>,
+    phrases: Res<Phrases>,
+    packs: Res<Assets<PhrasePack>>,
+) {
+    if game.path.info_type() == InfoType::Fine {
+        global_data.fine_daily = game.daily;
+        global_data.fine_index = game.path.phrase_index().unwrap();
+        global_data.fine_steps = game.path.steps();
+        global_data.fine_tree = std::mem::take(&mut game.tree);
+        global_data.fine_score = std::mem::take(&mut game.score);
+
+        let pack = phrases.get(&packs).unwrap();
+        global_data.fine_best = codex.record_today(
+            &pack.phrases[global_data.fine_index].id,
+            global_data.fine_steps,
+            global_data.fine_score.score(),
+        );
+    }
+
+    // Runs left before any choice was made are not worth watching, and would
+    // push real ones out of the replays kept.
+    let replay = game.replay.take().filter(|replay| !replay.steps.is_empty());
+    if let Some(replay) = replay {
+        if let Err(error) = replay.save() {
+            warn!("Could not save the replay: {}", error);
+        }
     }
 }
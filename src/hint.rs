@@ -0,0 +1,169 @@
+use super::utils::{graphemes, is_inputable, words};
+
+/// How long a flashed choice stays highlighted, in seconds.
+const FLASH_SECONDS: f64 = 0.6;
+
+/// The hints a player can ask for at a fork, each giving away more than the
+/// one before.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hint {
+    /// Highlights a choice that continues a phrase for a moment.
+    Flash,
+    /// Shows the rest of the phrase as blanks, a blank per letter.
+    Blanks,
+    /// Shows the rest of the next word.
+    Word,
+}
+
+impl Hint {
+    /// The points the hint takes off the score.
+    pub fn cost(&self) -> u32 {
+        match self {
+            Hint::Flash => 25,
+            Hint::Blanks => 50,
+            Hint::Word => 100,
+        }
+    }
+
+    /// The hint asked for after `self` at the same fork, if any.
+    fn next(&self) -> Option<Self> {
+        match self {
+            Hint::Flash => Some(Hint::Blanks),
+            Hint::Blanks => Some(Hint::Word),
+            Hint::Word => None,
+        }
+    }
+}
+
+/// The hints shown during a run.
+///
+/// Asking again at the same fork gives the next hint. Blanks stay for the
+/// rest of the run once taken, so they are skipped afterwards.
+#[derive(Debug, Default)]
+pub struct Hints {
+    /// The last hint taken at the current fork.
+    level: Option<Hint>,
+    /// The choice flashed, and until when, in seconds since startup.
+    flash: Option<(String, f64)>,
+    blanks: bool,
+    /// The text walked so far followed by the revealed word, shown until the
+    /// path walks past it or away from it.
+    word: Option<String>,
+}
+
+impl Hints {
+    /// The hint the player would get by asking now, if any is left.
+    pub fn next(&self) -> Option<Hint> {
+        let hint = match self.level {
+            None => Hint::Flash,
+            Some(level) => level.next()?,
+        };
+        match hint {
+            Hint::Blanks if self.blanks => hint.next(),
+            _ => Some(hint),
+        }
+    }
+
+    /// Takes the next hint, flashing `choice` or revealing the next word of
+    /// `rest`, the text left after `input`, as needed.
+    pub fn take(&mut self, choice: &str, input: &str, rest: &str, now: f64) -> Option<Hint> {
+        let hint = self.next()?;
+        match hint {
+            Hint::Flash => self.flash = Some((choice.to_string(), now + FLASH_SECONDS)),
+            Hint::Blanks => self.blanks = true,
+            Hint::Word => self.word = Some(format!("{}{}", input, next_word(rest))),
+        }
+        self.level = Some(hint);
+        Some(hint)
+    }
+
+    /// Moves on to another fork, where hints start over from the first.
+    pub fn next_fork(&mut self) {
+        self.level = None;
+        self.flash = None;
+    }
+
+    /// The choice flashed at `now`, if any.
+    pub fn flashed(&self, now: f64) -> Option<&str> {
+        match &self.flash {
+            Some((choice, until)) if now < *until => Some(choice),
+            _ => None,
+        }
+    }
+
+    pub fn shows_blanks(&self) -> bool {
+        self.blanks
+    }
+
+    /// The part of the revealed word not walked yet, after `input`.
+    pub fn revealed_word(&self, input: &str) -> Option<&str> {
+        let word = self.word.as_ref()?.strip_prefix(input)?;
+        (!word.is_empty()).then_some(word)
+    }
+}
+
+/// `text` up to the end of its first word, whatever leads up to it included.
+pub fn next_word(text: &str) -> String {
+    let mut word = String::new();
+    for unit in words(text) {
+        word.push_str(&unit);
+        if is_inputable(&unit) {
+            break;
+        }
+    }
+    word
+}
+
+/// `text` with a blank for every letter, spaces and punctuation kept.
+pub fn blanks(text: &str) -> String {
+    graphemes(text)
+        .into_iter()
+        .map(|grapheme| {
+            if is_inputable(&grapheme) {
+                "_".to_string()
+            } else {
+                grapheme
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_more_away_at_every_ask() {
+        let mut hints = Hints::default();
+        assert_eq!(hints.take("h", "be ", "healthy", 0.0), Some(Hint::Flash));
+        assert_eq!(hints.flashed(0.5), Some("h"));
+        assert_eq!(hints.flashed(1.0), None);
+        assert_eq!(hints.take("h", "be ", "healthy", 1.0), Some(Hint::Blanks));
+        assert_eq!(hints.take("h", "be ", "healthy", 2.0), Some(Hint::Word));
+        assert_eq!(hints.take("h", "be ", "healthy", 3.0), None);
+
+        // Blanks are kept, so the next fork goes from flashing to the word.
+        hints.next_fork();
+        assert!(hints.shows_blanks());
+        assert_eq!(hints.take("e", "be h", "ealthy", 4.0), Some(Hint::Flash));
+        assert_eq!(hints.next(), Some(Hint::Word));
+    }
+
+    #[test]
+    fn reveals_the_word_until_walked_past() {
+        let mut hints = Hints::default();
+        hints.take("i", "closure ", "is used", 0.0);
+        hints.take("i", "closure ", "is used", 0.0);
+        hints.take("i", "closure ", "is used", 0.0);
+        assert_eq!(hints.revealed_word("closure "), Some("is"));
+        assert_eq!(hints.revealed_word("closure i"), Some("s"));
+        assert_eq!(hints.revealed_word("closure is "), None);
+        assert_eq!(hints.revealed_word("closure x"), None);
+    }
+
+    #[test]
+    fn blanks_letters_only() {
+        assert_eq!(next_word(", used in"), ", used");
+        assert_eq!(blanks("ed in Javascript!"), "__ __ __________!");
+    }
+}
@@ -0,0 +1,660 @@
+use super::data::{PhrasePack, Phrases};
+use super::preferences::Preferences;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const DEFAULT_FONT_PATH: &str = "fonts/FiraMono-Medium.ttf";
+const FALLBACK_LOCALE: &str = "en-US";
+const MANIFEST_PATH: &str = "locales/manifest.locales.ron";
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<LocaleManifest>()
+            .add_asset::<Messages>()
+            .init_asset_loader::<LocaleManifestLoader>()
+            .init_asset_loader::<MessagesLoader>()
+            .add_startup_system(load_locales)
+            .add_system(update_localization);
+    }
+}
+
+/// Lists the locales shipped under `assets/locales/`.
+#[derive(Debug, Deserialize, TypeUuid)]
+#[uuid = "4b3e9f0a-6f57-4d39-9a43-0f1de8d1c2a7"]
+pub struct LocaleManifest {
+    pub locales: Vec<LocaleInfo>,
+}
+
+impl LocaleManifest {
+    /// The locales to look a message up in, most specific first.
+    ///
+    /// `de-AT` resolves to `de-AT` if it is shipped, then any other `de`
+    /// locale, then the fallback locale.
+    fn chain(&self, id: &str) -> Vec<&LocaleInfo> {
+        let language = language_of(id);
+        let mut chain = Vec::new();
+        let exact = self
+            .locales
+            .iter()
+            .filter(|info| info.id.eq_ignore_ascii_case(id));
+        let same_language = self
+            .locales
+            .iter()
+            .filter(|info| language_of(&info.id).eq_ignore_ascii_case(language));
+        let fallback = self
+            .locales
+            .iter()
+            .filter(|info| info.id == FALLBACK_LOCALE);
+        for info in exact.chain(same_language).chain(fallback) {
+            if !chain.iter().any(|added: &&LocaleInfo| added.id == info.id) {
+                chain.push(info);
+            }
+        }
+        chain
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LocaleInfo {
+    /// A BCP 47 tag such as `en-US`, also the name of its folder.
+    pub id: String,
+    /// The name shown in the language picker, in the language itself.
+    pub name: String,
+    /// A font covering the locale's script, if the default one does not.
+    #[serde(default)]
+    pub font: Option<String>,
+    /// The phrase pack to seek in this locale.
+    #[serde(default)]
+    pub phrases: Option<String>,
+}
+
+/// The messages of one locale, parsed from a Fluent (`.ftl`) file.
+///
+/// Only the subset of Fluent the game needs is supported: messages and
+/// terms, multiline values, comments, `{ $variable }` placeables, `{ -term }`
+/// references without arguments or attributes, and select expressions on a
+/// variable, which may not be nested.
+#[derive(Debug, TypeUuid)]
+#[uuid = "e1f7a0d2-3c55-4f0e-8c1b-5a2e2b9d7f64"]
+pub struct Messages(HashMap<String, String>);
+
+impl Messages {
+    pub fn parse(source: &str) -> Self {
+        let mut messages = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+
+        for line in source.lines() {
+            let trimmed = line.trim();
+            let is_indented = line.starts_with(char::is_whitespace);
+            if trimmed.starts_with('#') || (is_indented && trimmed.starts_with('.')) {
+                continue;
+            }
+            if is_indented {
+                if let Some((_, value)) = current.as_mut() {
+                    if !trimmed.is_empty() {
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(trimmed);
+                    }
+                }
+                continue;
+            }
+
+            if let Some((key, value)) = current.take() {
+                messages.insert(key, value);
+            }
+            if let Some((key, value)) = trimmed.split_once('=') {
+                current = Some((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        if let Some((key, value)) = current {
+            messages.insert(key, value);
+        }
+
+        Self(messages)
+    }
+}
+
+/// Substitutes the placeables of `message` with `args`, picking the variants
+/// of select expressions by the plural rules of `language`.
+fn format_message(message: &str, args: &[(&str, &str)], language: &str) -> String {
+    let mut formatted = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(start) = rest.find('{') {
+        let end = match closing_brace(&rest[start..]) {
+            Some(end) => start + end,
+            None => break,
+        };
+        formatted.push_str(&rest[..start]);
+        let placeable = rest[start + 1..end].trim();
+        match placeable.split_once("->") {
+            Some((selector, variants)) => {
+                let value = arg_value(selector.trim(), args);
+                let variant = select_variant(variants, value, language);
+                formatted.push_str(&format_message(&variant, args, language));
+            }
+            None => match arg_value(placeable, args) {
+                Some(value) => formatted.push_str(value),
+                None => formatted.push_str(placeable.trim_matches('"')),
+            },
+        }
+        rest = &rest[end + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+/// Where the placeable `text` starts with ends, past the placeables nested in
+/// the variants of a select expression and a `"}"` string literal.
+fn closing_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    // String literals can only open a placeable, while quotes elsewhere are
+    // text.
+    let mut opened = false;
+    let mut in_literal = false;
+    for (index, char) in text.char_indices() {
+        match char {
+            '"' if in_literal || opened => in_literal = !in_literal,
+            _ if in_literal => {}
+            '{' => {
+                depth += 1;
+                opened = true;
+                continue;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+        if !char.is_whitespace() {
+            opened = false;
+        }
+    }
+    None
+}
+
+/// The value of `$name` in `args`.
+fn arg_value<'a>(expression: &str, args: &[(&str, &'a str)]) -> Option<&'a str> {
+    let name = expression.strip_prefix('$')?;
+    args.iter()
+        .find(|(arg, _)| *arg == name)
+        .map(|(_, value)| *value)
+}
+
+/// The variant of a select expression matching `value`: the one keyed by the
+/// value itself, such as `[0]`, then by its plural category, such as `[one]`,
+/// then the default one marked with `*`.
+fn select_variant(variants: &str, value: Option<&str>, language: &str) -> String {
+    let mut parsed: Vec<(bool, &str, String)> = Vec::new();
+    for line in variants
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let (default, line) = match line.strip_prefix('*') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let variant = line.strip_prefix('[').and_then(|line| line.split_once(']'));
+        match (variant, parsed.last_mut()) {
+            (Some((key, text)), _) => parsed.push((default, key.trim(), text.trim().to_string())),
+            // Variants may span several lines.
+            (None, Some((_, _, text))) => {
+                text.push('\n');
+                text.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+
+    let category = value.and_then(|value| plural_category(language, value));
+    let find = |key: Option<&str>| parsed.iter().find(|(_, variant, _)| Some(*variant) == key);
+    find(value)
+        .or_else(|| find(category))
+        .or_else(|| parsed.iter().find(|(default, _, _)| *default))
+        .or_else(|| parsed.first())
+        .map(|(_, _, text)| text.clone())
+        .unwrap_or_default()
+}
+
+/// The CLDR cardinal plural category of `number` in `language`, or `None` if
+/// it is no number.
+///
+/// The rules are those of the CLDR plural tables for the language families
+/// below, written on the integer digits `i` and the number of visible
+/// fraction digits `v`, so that `1.0` is no `one` in English. Languages left
+/// out follow English.
+fn plural_category(language: &str, number: &str) -> Option<&'static str> {
+    number.parse::<f64>().ok()?;
+    let digits = number.trim_start_matches(['-', '+']);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let i = integer.parse::<u64>().unwrap_or(u64::MAX);
+    let v = fraction.len();
+    let (i10, i100) = (i % 10, i % 100);
+    let category = match language.to_ascii_lowercase().as_str() {
+        "id" | "ja" | "km" | "ko" | "lo" | "ms" | "my" | "th" | "vi" | "zh" => "other",
+        "fr" | "pt" if i < 2 => "one",
+        "ru" | "uk" | "be" if v != 0 => "other",
+        "ru" | "uk" | "be" if i10 == 1 && i100 != 11 => "one",
+        "pl" if v != 0 => "other",
+        "pl" if i == 1 => "one",
+        "ru" | "uk" | "be" | "pl" if (2..=4).contains(&i10) && !(12..=14).contains(&i100) => "few",
+        "ru" | "uk" | "be" => "many",
+        "pl" => "many",
+        "cs" | "sk" if v != 0 => "many",
+        "cs" | "sk" if (2..=4).contains(&i) => "few",
+        "fr" | "pt" => "other",
+        _ if i == 1 && v == 0 => "one",
+        _ => "other",
+    };
+    Some(category)
+}
+
+/// How deep terms may reference other terms, which also stops cycles.
+const MAX_TERM_DEPTH: usize = 8;
+
+/// Replaces the `{ -term }` references of every message with the term's
+/// value. References to missing terms are left as they are, for translators
+/// to see.
+fn resolve_terms(strings: &mut HashMap<String, String>) {
+    let terms = strings
+        .iter()
+        .filter(|(key, _)| key.starts_with('-'))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<HashMap<_, _>>();
+    if terms.is_empty() {
+        return;
+    }
+    for value in strings.values_mut() {
+        *value = with_terms(value, &terms, 0);
+    }
+}
+
+fn with_terms(text: &str, terms: &HashMap<String, String>, depth: usize) -> String {
+    let mut resolved = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        resolved.push_str(&rest[..start]);
+        let inner = rest[start + 1..].trim_start();
+        let name_length = inner
+            .char_indices()
+            .skip(1)
+            .find(|(_, char)| !(char.is_alphanumeric() || *char == '-' || *char == '_'))
+            .map_or(inner.len(), |(index, _)| index);
+        let (name, after) = inner.split_at(name_length);
+        let term = after
+            .trim_start()
+            .strip_prefix('}')
+            .filter(|_| depth < MAX_TERM_DEPTH)
+            .and_then(|after| Some((terms.get(name)?, after)));
+        match term {
+            Some((value, after)) if name.starts_with('-') => {
+                resolved.push_str(&with_terms(value, terms, depth + 1));
+                rest = after;
+            }
+            _ => {
+                resolved.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    resolved.push_str(rest);
+    resolved
+}
+
+fn language_of(id: &str) -> &str {
+    id.split(['-', '_']).next().unwrap_or(id)
+}
+
+/// The locale picked by the player and the strings resolved for it.
+pub struct Localization {
+    manifest: Handle<LocaleManifest>,
+    messages: HashMap<String, Handle<Messages>>,
+    requested: String,
+    resolved: Option<String>,
+    strings: HashMap<String, String>,
+    font: String,
+}
+
+impl Localization {
+    /// Whether the strings of the requested locale are ready to be shown.
+    pub fn is_ready(&self) -> bool {
+        self.resolved.as_ref() == Some(&self.requested)
+    }
+
+    pub fn font(&self) -> &str {
+        &self.font
+    }
+
+    /// Whether the locale brings a font of its own, for a script the default
+    /// font does not cover.
+    pub fn has_script_font(&self) -> bool {
+        self.font != DEFAULT_FONT_PATH
+    }
+
+    pub fn get(&self, key: &str) -> &str {
+        match self.strings.get(key) {
+            Some(message) => message,
+            // Show the missing key to translators, but nothing while loading.
+            None if self.strings.is_empty() => "",
+            None => key,
+        }
+    }
+
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let locale = self.resolved.as_deref().unwrap_or(FALLBACK_LOCALE);
+        format_message(self.get(key), args, language_of(locale))
+    }
+
+    /// The id of the locale picked, which may still be loading.
+    pub fn requested(&self) -> &str {
+        &self.requested
+    }
+
+    /// The name of the requested locale as shown in the language picker.
+    pub fn locale_name<'a>(&'a self, manifests: &'a Assets<LocaleManifest>) -> &'a str {
+        manifests
+            .get(&self.manifest)
+            .and_then(|manifest| {
+                manifest
+                    .locales
+                    .iter()
+                    .find(|info| info.id == self.requested)
+            })
+            .map_or(&self.requested, |info| &info.name)
+    }
+
+    /// Requests the locale following the current one in the manifest.
+    pub fn cycle_locale(&mut self, manifests: &Assets<LocaleManifest>) {
+        if let Some(manifest) = manifests.get(&self.manifest) {
+            let count = manifest.locales.len();
+            let index = manifest
+                .locales
+                .iter()
+                .position(|info| info.id == self.requested);
+            let next = index.map_or(0, |index| (index + 1) % count);
+            if let Some(info) = manifest.locales.get(next) {
+                self.requested = info.id.clone();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LocaleManifestLoader;
+
+impl AssetLoader for LocaleManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let manifest = ron::de::from_bytes::<LocaleManifest>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["locales.ron"]
+    }
+}
+
+#[derive(Default)]
+pub struct MessagesLoader;
+
+impl AssetLoader for MessagesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let messages = Messages::parse(std::str::from_utf8(bytes)?);
+            load_context.set_default_asset(LoadedAsset::new(messages));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+fn load_locales(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    preferences: Res<Preferences>,
+) {
+    let requested = preferences
+        .language
+        .clone()
+        .or_else(system_locale)
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string());
+    commands.insert_resource(Localization {
+        manifest: asset_server.load(MANIFEST_PATH),
+        messages: HashMap::new(),
+        requested,
+        resolved: None,
+        strings: HashMap::new(),
+        font: DEFAULT_FONT_PATH.to_string(),
+    });
+}
+
+/// Reads the locale of the desktop session, e.g. `de_DE.UTF-8` as `de-DE`.
+fn system_locale() -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let lang = std::env::var("LC_ALL")
+            .or_else(|_| std::env::var("LANG"))
+            .ok()?;
+        let id = lang.split('.').next()?.replace('_', "-");
+        if !id.is_empty() && id != "C" && id != "POSIX" {
+            return Some(id);
+        }
+    }
+    None
+}
+
+fn update_localization(
+    asset_server: Res<AssetServer>,
+    mut localization: ResMut<Localization>,
+    mut phrases: ResMut<Phrases>,
+    manifests: Res<Assets<LocaleManifest>>,
+    messages: Res<Assets<Messages>>,
+    mut message_events: EventReader<AssetEvent<Messages>>,
+) {
+    let reloaded = message_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if localization.is_ready() && !reloaded {
+        return;
+    }
+    let manifest = match manifests.get(&localization.manifest) {
+        Some(manifest) => manifest,
+        None => return,
+    };
+
+    let chain = manifest.chain(&localization.requested);
+    for info in chain.iter() {
+        if !localization.messages.contains_key(&info.id) {
+            let handle = asset_server.load(format!("locales/{}/main.ftl", info.id).as_str());
+            localization.messages.insert(info.id.clone(), handle);
+        }
+    }
+    let chain_messages = chain
+        .iter()
+        .map(|info| messages.get(&localization.messages[&info.id]))
+        .collect::<Option<Vec<_>>>();
+    let chain_messages = match chain_messages {
+        Some(chain_messages) => chain_messages,
+        None => return,
+    };
+
+    let mut strings = HashMap::new();
+    for locale_messages in chain_messages.iter().rev() {
+        strings.extend(locale_messages.0.clone());
+    }
+    resolve_terms(&mut strings);
+    localization.strings = strings;
+    localization.font = chain
+        .iter()
+        .find_map(|info| info.font.clone())
+        .unwrap_or_else(|| DEFAULT_FONT_PATH.to_string());
+    if let Some(path) = chain.iter().find_map(|info| info.phrases.as_deref()) {
+        let pack: Handle<PhrasePack> = asset_server.load(path);
+        if phrases.pack != pack {
+            phrases.pack = pack;
+        }
+    }
+    // Settle on the locale actually shipped, e.g. `de-DE` for `de-AT`.
+    if let Some(info) = chain.first() {
+        localization.requested = info.id.clone();
+    }
+    localization.resolved = Some(localization.requested.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(id: &str) -> LocaleInfo {
+        LocaleInfo {
+            id: id.to_string(),
+            name: id.to_string(),
+            font: None,
+            phrases: None,
+        }
+    }
+
+    fn ids(chain: Vec<&LocaleInfo>) -> Vec<&str> {
+        chain.into_iter().map(|info| info.id.as_str()).collect()
+    }
+
+    #[test]
+    fn falls_back_to_the_language_then_the_default_locale() {
+        let manifest = LocaleManifest {
+            locales: vec![locale("en-US"), locale("de-DE"), locale("zh-CN")],
+        };
+        assert_eq!(ids(manifest.chain("de-DE")), ["de-DE", "en-US"]);
+        assert_eq!(ids(manifest.chain("de-AT")), ["de-DE", "en-US"]);
+        assert_eq!(ids(manifest.chain("zh")), ["zh-CN", "en-US"]);
+        assert_eq!(ids(manifest.chain("fr-FR")), ["en-US"]);
+        assert_eq!(ids(manifest.chain("en-US")), ["en-US"]);
+    }
+
+    #[test]
+    fn parses_messages() {
+        let messages = Messages::parse(
+            "# Splash screen\n\
+             splash-start = Press Enter\n\
+             game-tedious =\n    Wait...\n    Try Esc\n\
+             \n\
+             fine-steps = { $steps } steps\n    .title = ignored\n",
+        );
+        assert_eq!(messages.0["splash-start"], "Press Enter");
+        assert_eq!(messages.0["game-tedious"], "Wait...\nTry Esc");
+        assert_eq!(messages.0["fine-steps"], "{ $steps } steps");
+        assert_eq!(messages.0.len(), 3);
+    }
+
+    #[test]
+    fn formats_placeables() {
+        assert_eq!(
+            format_message(
+                "{ $steps } steps in {$lang}",
+                &[("steps", "12"), ("lang", "de")],
+                "en"
+            ),
+            "12 steps in de"
+        );
+        assert_eq!(format_message("a { \"{\" } b", &[], "en"), "a { b");
+        assert_eq!(format_message("a { \"}\" } b", &[], "en"), "a } b");
+        assert_eq!(
+            format_message("unclosed { $x", &[("x", "1")], "en"),
+            "unclosed { $x"
+        );
+    }
+
+    #[test]
+    fn selects_variants_by_plural_category() {
+        let messages = Messages::parse(
+            "fine-steps = in { $steps ->\n        \
+             [0] no \"time\"\n        \
+             [one] { $steps } step\n       \
+             *[other] { $steps } steps\n    \
+             }!\n",
+        );
+        let message = &messages.0["fine-steps"];
+        let format = |steps, language| format_message(message, &[("steps", steps)], language);
+        assert_eq!(format("0", "en"), "in no \"time\"!");
+        assert_eq!(format("1", "en"), "in 1 step!");
+        assert_eq!(format("12", "en"), "in 12 steps!");
+        assert_eq!(format("1", "zh"), "in 1 steps!");
+        assert_eq!(format("1.0", "en"), "in 1.0 steps!");
+        assert_eq!(format("abc", "en"), "in abc steps!");
+    }
+
+    #[test]
+    fn follows_the_plural_rules_of_the_language() {
+        let categories = |language, numbers: &[&str]| {
+            numbers
+                .iter()
+                .map(|number| plural_category(language, number).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            categories("de", &["0", "1", "2", "1.5"]),
+            ["other", "one", "other", "other"]
+        );
+        assert_eq!(categories("fr", &["0", "1", "2"]), ["one", "one", "other"]);
+        assert_eq!(
+            categories("ru", &["1", "3", "5", "11", "21", "22", "1.5"]),
+            ["one", "few", "many", "many", "one", "few", "other"]
+        );
+        assert_eq!(
+            categories("pl", &["1", "3", "5", "21", "22", "2.5"]),
+            ["one", "few", "many", "many", "few", "other"]
+        );
+        assert_eq!(categories("zh", &["1", "2"]), ["other", "other"]);
+        assert_eq!(plural_category("en", "many"), None);
+    }
+
+    #[test]
+    fn resolves_term_references() {
+        let mut strings = Messages::parse(
+            "-brand = Ashgrove\n\
+             -tagline = {-brand}, a maze\n\
+             splash-title = Welcome to { -brand }!\n\
+             splash-about = { -tagline } in { $steps ->\n        \
+             [one] one { -brand } step\n       \
+             *[other] { $steps } steps\n    \
+             }\n\
+             splash-missing = { -nothing } and { $key }\n\
+             -loop = { -loop }\n\
+             splash-loop = { -loop }\n",
+        )
+        .0;
+        resolve_terms(&mut strings);
+        assert_eq!(strings["splash-title"], "Welcome to Ashgrove!");
+        assert_eq!(
+            format_message(&strings["splash-about"], &[("steps", "1")], "en"),
+            "Ashgrove, a maze in one Ashgrove step"
+        );
+        assert_eq!(strings["splash-missing"], "{ -nothing } and { $key }");
+        assert_eq!(strings["splash-loop"], "{ -loop }");
+    }
+}
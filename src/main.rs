@@ -2,15 +2,33 @@
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+mod announce;
+mod codex;
+mod controls;
+mod daily;
 mod data;
+mod decoy;
 mod fine;
 mod game;
+mod hint;
+mod locale;
+mod path;
+mod preferences;
+mod replay;
+mod rng;
+mod score;
+mod settings;
 mod splash;
+mod storage;
+mod theme;
+mod timer;
+mod tree;
+mod trie;
 mod utils;
 
 use bevy::{
-    asset::AssetPlugin, core::CorePlugin, core_pipeline::CorePipelinePlugin, input::InputPlugin,
-    prelude::*, render::RenderPlugin, sprite::SpritePlugin, text::TextPlugin,
+    asset::AssetPlugin, core::CorePlugin, core_pipeline::CorePipelinePlugin, gilrs::GilrsPlugin,
+    input::InputPlugin, prelude::*, render::RenderPlugin, sprite::SpritePlugin, text::TextPlugin,
     transform::TransformPlugin, ui::UiPlugin, window::WindowPlugin, winit::WinitPlugin,
 };
 
@@ -19,29 +37,43 @@ enum GameState {
     Splash,
     Game,
     Fine,
+    Replay,
+    Codex,
+    Settings,
 }
 
 #[derive(Default)]
 struct GlobalData {
+    fine_daily: Option<daily::Daily>,
+    fine_best: bool,
     fine_index: usize,
+    fine_score: score::ScoreCard,
+    fine_steps: usize,
+    fine_tree: tree::BranchTree,
 }
 
 #[derive(Component)]
 struct PrepareText;
 
 fn main() {
+    // The web build fills the page, which phones hold upright.
+    let (width, height) = page_size().unwrap_or((480., 480.));
+
     App::new()
         .insert_resource(ClearColor(Color::rgb(0.0, 0.0, 0.0)))
         .insert_resource(WindowDescriptor {
-            width: 480.,
-            height: 480.,
+            width,
+            height,
             vsync: true,
             ..Default::default()
         })
+        .insert_resource(rng::GameRng::new(rng::requested_code()))
+        .insert_resource(game::ReplayViewer::new(replay::requested_replay()))
         .init_resource::<GlobalData>()
         .add_plugin(CorePlugin)
         .add_plugin(TransformPlugin)
         .add_plugin(InputPlugin)
+        .add_plugin(GilrsPlugin)
         .add_plugin(WindowPlugin::default())
         .add_plugin(AssetPlugin)
         .add_plugin(WinitPlugin)
@@ -50,11 +82,19 @@ fn main() {
         .add_plugin(SpritePlugin)
         .add_plugin(TextPlugin)
         .add_plugin(UiPlugin)
+        .add_plugin(data::DataPlugin)
+        .add_plugin(locale::LocalePlugin)
+        .add_plugin(controls::ControlsPlugin)
+        .add_plugin(theme::ThemePlugin)
+        .add_plugin(announce::AnnouncePlugin)
         .add_startup_system(setup)
+        .add_system(fit_page)
         .add_state(GameState::Splash)
         .add_plugin(splash::SplashPlugin)
         .add_plugin(game::GamePlugin)
         .add_plugin(fine::FinePlugin)
+        .add_plugin(codex::CodexPlugin)
+        .add_plugin(settings::SettingsPlugin)
         .run();
 }
 
@@ -62,6 +102,37 @@ fn setup(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
 }
 
+/// The size of the page the game is embedded in, on the web.
+fn page_size() -> Option<(f32, f32)> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        None
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let window = web_sys::window()?;
+        let width = window.inner_width().ok()?.as_f64()?;
+        let height = window.inner_height().ok()?.as_f64()?;
+        Some((width as f32, height as f32))
+    }
+}
+
+/// Resizes the window along with the page, e.g. when a phone is turned.
+fn fit_page(mut windows: ResMut<Windows>) {
+    if let (Some(window), Some((width, height))) = (windows.get_primary_mut(), page_size()) {
+        if window.width() != width || window.height() != height {
+            window.set_resolution(width, height);
+        }
+    }
+}
+
+/// Whether the window is taller than wide, as on a phone held upright.
+fn is_portrait(windows: &Windows) -> bool {
+    windows
+        .get_primary()
+        .map_or(false, |window| window.height() > window.width())
+}
+
 fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in to_despawn.iter() {
         commands.entity(entity).despawn_recursive();
@@ -0,0 +1,968 @@
+use super::decoy::Decoys;
+use super::trie::PhraseTrie;
+use super::utils::{clauses, fold_case, graphemes, is_inputable, to_lowercase, words};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How many steps a player may wander off every phrase before being told so.
+const TEDIOUS_STEPS: usize = 10;
+
+pub const MIN_BRANCHES: usize = 2;
+pub const MAX_BRANCHES: usize = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InfoType {
+    #[default]
+    None,
+    Fine,
+    Neverending,
+    Tedious,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// The char is not one of the current choices, nothing has changed.
+    Ignored,
+    /// The char has been taken, leaving the path in the given state.
+    Chosen(InfoType),
+}
+
+/// How much of a phrase every fork offers.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum Granularity {
+    #[default]
+    Letter,
+    Word,
+    /// Everything up to the next punctuation mark or line break.
+    Clause,
+}
+
+impl Granularity {
+    pub const ALL: [Granularity; 3] = [Granularity::Letter, Granularity::Word, Granularity::Clause];
+
+    /// The key of the localized name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Granularity::Letter => "granularity-letter",
+            Granularity::Word => "granularity-word",
+            Granularity::Clause => "granularity-clause",
+        }
+    }
+
+    /// The next granularity, coarser, wrapping back to letters.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|g| g == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Splits `text` into the units offered at forks, the text between them
+    /// (spaces, punctuation) being units of its own that are filled in.
+    pub fn split(&self, text: &str) -> Vec<String> {
+        match self {
+            Granularity::Letter => graphemes(text),
+            Granularity::Word => words(text),
+            Granularity::Clause => clauses(text),
+        }
+    }
+}
+
+/// How some typed text relates to the current choices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputMatch {
+    /// The text picks the given choice.
+    Choice(String),
+    /// The text starts more than one choice, more input is needed.
+    Partial,
+    /// The text matches none of the choices.
+    None,
+}
+
+/// The rules of a single seeking, free of any engine state.
+///
+/// Every fork offers as many choices as there are branches, each a letter,
+/// word or clause depending on the granularity of the path. Every phrase
+/// the choices so far could still lead to stays alive, and a fork offers
+/// the distinct units that continue them, filled up with decoys. Choosing a
+/// decoy strays from every phrase; completing one reveals it. Until then
+/// the player may walk back up the path, one fork at a time.
+#[derive(Debug, Default)]
+pub struct ForkingPath {
+    branches: usize,
+    choices: Vec<String>,
+    /// Every phrase given, split into units.
+    datas: Vec<Vec<String>>,
+    decoys: Decoys,
+    granularity: Granularity,
+    /// The forks choices were taken at, latest last.
+    history: Vec<Fork>,
+    info_type: InfoType,
+    input_text: String,
+    /// The phrases still possible, as the trie node walked to so far. Left
+    /// where the player strayed.
+    node: usize,
+    seeking_index: usize,
+    steps: usize,
+    trie: PhraseTrie,
+}
+
+/// A fork as it was offered, kept to walk back to it.
+#[derive(Debug)]
+struct Fork {
+    choices: Vec<String>,
+    info_type: InfoType,
+    input_len: usize,
+    node: usize,
+    seeking_index: usize,
+}
+
+impl ForkingPath {
+    /// Starts a seeking with forks of `branches` choices, clamped between
+    /// [`MIN_BRANCHES`] and [`MAX_BRANCHES`], toward any of `phrases`.
+    ///
+    /// Choices beyond the units continuing the phrases are filled from
+    /// `decoys`, which should be built for the same `granularity`.
+    ///
+    /// Panics if fewer than two phrases are given.
+    pub fn new<S: AsRef<str>, R: Rng>(
+        phrases: &[S],
+        branches: usize,
+        granularity: Granularity,
+        decoys: Decoys,
+        rng: &mut R,
+    ) -> Self {
+        assert!(phrases.len() >= 2, "a seeking needs at least two phrases");
+        Self::start(
+            phrases,
+            0..phrases.len(),
+            branches,
+            granularity,
+            decoys,
+            rng,
+        )
+    }
+
+    /// Starts a seeking toward the phrase at `index` only, so that its first
+    /// unit is offered among decoys too. It should be one of
+    /// [`Self::revealable`].
+    pub fn toward<S: AsRef<str>, R: Rng>(
+        phrases: &[S],
+        index: usize,
+        branches: usize,
+        granularity: Granularity,
+        decoys: Decoys,
+        rng: &mut R,
+    ) -> Self {
+        Self::start(phrases, [index], branches, granularity, decoys, rng)
+    }
+
+    fn start<S, I, R>(
+        phrases: &[S],
+        candidates: I,
+        branches: usize,
+        granularity: Granularity,
+        decoys: Decoys,
+        rng: &mut R,
+    ) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = usize>,
+        R: Rng,
+    {
+        let datas = split_all(phrases, granularity);
+        let candidates = candidates.into_iter().collect::<Vec<_>>();
+        let mut trie = PhraseTrie::default();
+        for index in revealable_among(&datas, &candidates) {
+            trie.insert(index, inputable_units(&datas[index]));
+        }
+        assert!(
+            !trie.phrases(PhraseTrie::ROOT).is_empty(),
+            "none of the phrases can be revealed"
+        );
+
+        let mut path = Self {
+            branches: branches.clamp(MIN_BRANCHES, MAX_BRANCHES),
+            datas,
+            decoys,
+            granularity,
+            trie,
+            ..Default::default()
+        };
+        path.fork(rng);
+        path
+    }
+
+    /// The indices of the `phrases` a seeking can reveal, split at
+    /// `granularity`. Left out are phrases with nothing to input, repeats of
+    /// an earlier phrase once punctuation and case are set aside, and phrases
+    /// a longer one starts with, as reaching their end would reveal them
+    /// before the longer one ever could be.
+    pub fn revealable<S: AsRef<str>>(phrases: &[S], granularity: Granularity) -> Vec<usize> {
+        let candidates = (0..phrases.len()).collect::<Vec<_>>();
+        revealable_among(&split_all(phrases, granularity), &candidates)
+    }
+
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+
+    pub fn choices(&self) -> &[String] {
+        &self.choices
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn info_type(&self) -> InfoType {
+        self.info_type
+    }
+
+    pub fn input_text(&self) -> &str {
+        &self.input_text
+    }
+
+    /// The index, in the given phrases, of the phrase revealed, or of the
+    /// only one the choices so far could still lead to.
+    pub fn phrase_index(&self) -> Option<usize> {
+        if self.info_type == InfoType::Fine {
+            return self.trie.ended(self.node);
+        }
+        match self.trie.phrases(self.node) {
+            [index] => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// How many forks the phrase of [`Self::phrase_index`] takes to reveal,
+    /// wandering aside.
+    pub fn forks(&self) -> Option<usize> {
+        let data = &self.datas[self.phrase_index()?];
+        Some(data.iter().filter(|c| is_inputable(c)).count())
+    }
+
+    pub fn seeking_index(&self) -> usize {
+        self.seeking_index
+    }
+
+    /// How many choices have been taken so far, walking back included.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Matches text typed by the player, in any case, against the choices.
+    pub fn match_input(&self, typed: &str) -> InputMatch {
+        let typed = fold_case(typed);
+        if typed.is_empty() {
+            return InputMatch::None;
+        }
+
+        let folded = self.choices.iter().map(|choice| fold_case(choice));
+        let started = folded
+            .zip(&self.choices)
+            .filter(|(folded, _)| folded.starts_with(&typed))
+            .collect::<Vec<_>>();
+        if let Some((_, choice)) = started.iter().find(|(folded, _)| *folded == typed) {
+            return InputMatch::Choice(choice.to_string());
+        }
+        match started.as_slice() {
+            [] => InputMatch::None,
+            [(_, choice)] => InputMatch::Choice(choice.to_string()),
+            _ => InputMatch::Partial,
+        }
+    }
+
+    pub fn choose<R: Rng>(&mut self, c: &str, rng: &mut R) -> Outcome {
+        if self.info_type == InfoType::Fine || !self.choices.iter().any(|choice| choice == c) {
+            return Outcome::Ignored;
+        }
+
+        self.history.push(Fork {
+            choices: self.choices.clone(),
+            info_type: self.info_type,
+            input_len: self.input_text.len(),
+            node: self.node,
+            seeking_index: self.seeking_index,
+        });
+        self.steps += 1;
+
+        let child = match self.info_type {
+            InfoType::None => self.trie.child(self.node, c),
+            _ => None,
+        };
+        match child {
+            Some(child) => {
+                self.node = child;
+                if let Some(index) = self.trie.ended(child) {
+                    // Fill in whatever punctuation ends the phrase.
+                    self.input_text = self.datas[index].concat().to_lowercase();
+                    self.info_type = InfoType::Fine;
+                    return Outcome::Chosen(self.info_type);
+                }
+            }
+            None => {
+                self.input_text.push_str(c);
+                if self.info_type == InfoType::None {
+                    self.info_type = InfoType::Neverending;
+                }
+            }
+        }
+        if self.info_type == InfoType::Neverending && self.seeking_index >= TEDIOUS_STEPS {
+            self.info_type = InfoType::Tedious;
+        }
+
+        self.seeking_index += 1;
+        self.fork(rng);
+
+        Outcome::Chosen(self.info_type)
+    }
+
+    /// Walks back to the fork the last choice was taken at, offering its
+    /// choices again. Straying is undone too, so that a lost player can find
+    /// the path again.
+    ///
+    /// Returns `false` if there is nothing to walk back to, or the path is
+    /// already revealed.
+    pub fn back(&mut self) -> bool {
+        if self.info_type == InfoType::Fine {
+            return false;
+        }
+        let fork = match self.history.pop() {
+            Some(fork) => fork,
+            None => return false,
+        };
+
+        self.choices = fork.choices;
+        self.info_type = fork.info_type;
+        self.input_text.truncate(fork.input_len);
+        self.node = fork.node;
+        self.seeking_index = fork.seeking_index;
+        self.steps += 1;
+        true
+    }
+
+    /// The choice continuing the phrase hints follow, unless the path strayed
+    /// or is revealed.
+    pub fn hint_choice(&self) -> Option<&str> {
+        if self.info_type != InfoType::None {
+            return None;
+        }
+        let unit = self.next_unit(self.hinted_phrase())?;
+        let choice = self.choices.iter().find(|choice| **choice == unit)?;
+        Some(choice)
+    }
+
+    /// What is left of the phrase hints follow past the input text, unless
+    /// the path strayed or is revealed.
+    pub fn rest(&self) -> Option<String> {
+        if self.info_type != InfoType::None {
+            return None;
+        }
+        let (_, rest) = self.split_walked(self.hinted_phrase());
+        Some(rest.concat().to_lowercase())
+    }
+
+    /// The phrase every hint follows: the first still possible that one of
+    /// the choices continues, as not every phrase's may be offered.
+    fn hinted_phrase(&self) -> usize {
+        let phrases = self.trie.phrases(self.node);
+        phrases
+            .iter()
+            .copied()
+            .find(|&phrase| {
+                self.next_unit(phrase)
+                    .map_or(false, |unit| self.choices.contains(&unit))
+            })
+            .unwrap_or(phrases[0])
+    }
+
+    /// The unit of `phrase` offered at the fork at `seeking_index`, as the
+    /// choices show it.
+    fn next_unit(&self, phrase: usize) -> Option<String> {
+        let (_, rest) = self.split_walked(phrase);
+        rest.first().map(|unit| to_lowercase(unit))
+    }
+
+    /// Splits `phrase` before the unit offered at the fork at
+    /// `seeking_index`.
+    fn split_walked(&self, phrase: usize) -> (&[String], &[String]) {
+        let data = &self.datas[phrase];
+        let mut walked = 0;
+        for (i, unit) in data.iter().enumerate() {
+            if is_inputable(unit) {
+                if walked == self.seeking_index {
+                    return data.split_at(i);
+                }
+                walked += 1;
+            }
+        }
+        (data, &[])
+    }
+
+    /// The text walked so far. The phrases still possible share its units,
+    /// but may part on the text between them: only the end of it they all
+    /// share is shown, such as the space of `be, h` and `be h`, until the
+    /// path narrows down to one of them.
+    fn walked_text(&self) -> String {
+        let walked = self
+            .trie
+            .phrases(self.node)
+            .iter()
+            .map(|&phrase| split_gaps(self.split_walked(phrase).0))
+            .collect::<Vec<_>>();
+        let (units, _) = &walked[0];
+        let gap = |index: usize| {
+            let gaps = walked
+                .iter()
+                .map(|(_, gaps)| gaps[index].as_str())
+                .collect::<Vec<_>>();
+            common_end(&gaps).to_string()
+        };
+        let mut text = String::new();
+        for (index, unit) in units.iter().enumerate() {
+            text.push_str(&gap(index));
+            text.push_str(unit);
+        }
+        text.push_str(&gap(units.len()));
+        text.to_lowercase()
+    }
+
+    /// Offers the choices of the fork at `seeking_index`, all distinct.
+    fn fork<R: Rng>(&mut self, rng: &mut R) {
+        let mut taken = Vec::with_capacity(self.branches);
+        if self.info_type == InfoType::None {
+            self.input_text = self.walked_text();
+            let next = self.trie.next_units(self.node);
+            let offered = next.len().min(self.branches);
+            for i in rand::seq::index::sample(rng, next.len(), offered) {
+                taken.push(next[i].to_string());
+            }
+        }
+
+        let context = self.granularity.split(&self.input_text.to_lowercase());
+        while taken.len() < self.branches {
+            let decoy = self.decoys.decoy(&context, &taken, rng);
+            taken.push(decoy);
+        }
+        taken.shuffle(rng);
+        self.choices = taken;
+    }
+}
+
+fn split_all<S: AsRef<str>>(phrases: &[S], granularity: Granularity) -> Vec<Vec<String>> {
+    phrases
+        .iter()
+        .map(|phrase| granularity.split(phrase.as_ref()))
+        .collect()
+}
+
+/// The units of a phrase offered at forks, as the choices show them.
+fn inputable_units(data: &[String]) -> Vec<String> {
+    data.iter()
+        .filter(|unit| is_inputable(unit))
+        .map(|unit| to_lowercase(unit))
+        .collect()
+}
+
+/// See [`ForkingPath::revealable`].
+fn revealable_among(datas: &[Vec<String>], candidates: &[usize]) -> Vec<usize> {
+    let mut trie = PhraseTrie::default();
+    for &index in candidates {
+        trie.insert(index, inputable_units(&datas[index]));
+    }
+    candidates
+        .iter()
+        .copied()
+        .filter(|&index| {
+            let units = inputable_units(&datas[index]);
+            let end = units
+                .iter()
+                .try_fold(PhraseTrie::ROOT, |node, unit| trie.child(node, unit));
+            !units.is_empty()
+                && end.map_or(false, |end| {
+                    trie.ended(end) == Some(index) && trie.next_units(end).is_empty()
+                })
+        })
+        .collect()
+}
+
+/// Splits `units` into the inputable ones and the text before each of them,
+/// followed by the text after the last one.
+fn split_gaps(units: &[String]) -> (Vec<&str>, Vec<String>) {
+    let mut inputable = Vec::new();
+    let mut gaps = Vec::new();
+    let mut gap = String::new();
+    for unit in units {
+        if is_inputable(unit) {
+            gaps.push(std::mem::take(&mut gap));
+            inputable.push(unit.as_str());
+        } else {
+            gap.push_str(unit);
+        }
+    }
+    gaps.push(gap);
+    (inputable, gaps)
+}
+
+/// The longest end `texts` share.
+fn common_end<'a>(texts: &[&'a str]) -> &'a str {
+    let first = texts[0];
+    let mut start = 0;
+    for text in &texts[1..] {
+        let shared = first
+            .chars()
+            .rev()
+            .zip(text.chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(char, _)| char.len_utf8())
+            .sum::<usize>();
+        start = start.max(first.len() - shared);
+    }
+    &first[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoy::DecoyKind;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const PHRASES: [&str; 2] = ["be healthy", "Closure is used in Javascript"];
+
+    /// Starts a path of letter forks with the default decoys, and the rng to
+    /// walk it with.
+    fn start(phrases: &[&str], branches: usize, seed: u64) -> (ForkingPath, StdRng) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let path = ForkingPath::new(
+            phrases,
+            branches,
+            Granularity::Letter,
+            Decoys::default(),
+            &mut rng,
+        );
+        (path, rng)
+    }
+
+    fn is_true(path: &ForkingPath, choice: &str) -> bool {
+        path.info_type == InfoType::None && path.trie.child(path.node, choice).is_some()
+    }
+
+    /// The first choice continuing one of the phrases still possible.
+    fn true_choice(path: &ForkingPath) -> String {
+        let mut choices = path.choices.iter();
+        choices
+            .find(|choice| is_true(path, choice))
+            .unwrap()
+            .clone()
+    }
+
+    fn decoy_choice(path: &ForkingPath) -> Option<String> {
+        let mut choices = path.choices.iter();
+        choices.find(|choice| !is_true(path, choice)).cloned()
+    }
+
+    /// Commits to "be healthy" and strays from it on the next fork.
+    fn stray(path: &mut ForkingPath, rng: &mut StdRng) {
+        assert_eq!(path.choose("b", rng), Outcome::Chosen(InfoType::None));
+        while path.info_type() == InfoType::None {
+            let c = decoy_choice(path).unwrap_or_else(|| true_choice(path));
+            path.choose(&c, rng);
+        }
+    }
+
+    /// Follows the true choice of every fork until the path is revealed.
+    fn follow(path: &mut ForkingPath, rng: &mut StdRng) {
+        while path.info_type() == InfoType::None {
+            path.choose(&true_choice(path), rng);
+        }
+    }
+
+    #[test]
+    fn starts_between_two_distinct_phrases() {
+        for seed in 0..32 {
+            let (path, _) = start(&PHRASES, 2, seed);
+            let mut choices = path.choices().to_vec();
+            choices.sort_unstable();
+            assert_eq!(choices, ["b", "c"]);
+            assert_eq!(path.info_type(), InfoType::None);
+            assert_eq!(path.phrase_index(), None);
+        }
+    }
+
+    #[test]
+    fn ignores_chars_that_are_not_choices() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 0);
+        assert_eq!(path.forks(), None);
+        assert_eq!(path.choose("z", &mut rng), Outcome::Ignored);
+        assert_eq!(path.input_text(), "");
+        assert_eq!(path.steps(), 0);
+    }
+
+    #[test]
+    fn first_choice_commits_to_a_phrase() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 0);
+        assert_eq!(path.choose("c", &mut rng), Outcome::Chosen(InfoType::None));
+        assert_eq!(path.phrase_index(), Some(1));
+        assert_eq!(true_choice(&path), "l");
+    }
+
+    #[test]
+    fn following_the_phrase_reveals_it_for_good() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 1);
+        path.choose("b", &mut rng);
+        follow(&mut path, &mut rng);
+        assert_eq!(path.info_type(), InfoType::Fine);
+        assert_eq!(path.input_text(), "be healthy");
+        assert_eq!(path.steps(), 9);
+        assert_eq!(path.forks(), Some(9));
+        assert_eq!(path.phrase_index(), Some(0));
+
+        let choice = path.choices()[0].clone();
+        assert_eq!(path.choose(&choice, &mut rng), Outcome::Ignored);
+        assert!(!path.back());
+        assert_eq!(path.input_text(), "be healthy");
+    }
+
+    #[test]
+    fn straying_is_neverending_then_tedious() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 4);
+        stray(&mut path, &mut rng);
+        assert_eq!(path.info_type(), InfoType::Neverending);
+
+        let choice = path.choices()[1].clone();
+        assert_eq!(
+            path.choose(&choice, &mut rng),
+            Outcome::Chosen(InfoType::Neverending)
+        );
+        while path.info_type() == InfoType::Neverending {
+            assert!(path.seeking_index() <= TEDIOUS_STEPS);
+            let choice = path.choices()[0].clone();
+            path.choose(&choice, &mut rng);
+        }
+        assert_eq!(path.info_type(), InfoType::Tedious);
+    }
+
+    #[test]
+    fn tedious_path_is_never_revealed() {
+        let (mut path, mut rng) = start(&["b is for a rather long phrase", "c"], 2, 6);
+        stray(&mut path, &mut rng);
+        for _ in 0..64 {
+            let choice = path.choices()[0].clone();
+            let outcome = path.choose(&choice, &mut rng);
+            assert_ne!(outcome, Outcome::Chosen(InfoType::Fine));
+        }
+        assert_eq!(path.info_type(), InfoType::Tedious);
+    }
+
+    #[test]
+    fn walking_back_offers_the_last_fork_again() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 16);
+        assert!(!path.back());
+
+        path.choose("c", &mut rng);
+        for _ in 0.."losure".len() {
+            path.choose(&true_choice(&path), &mut rng);
+        }
+        // The space is not offered, and filled in instead.
+        assert_eq!(path.input_text(), "closure ");
+        let choices = path.choices().to_vec();
+        path.choose(&true_choice(&path), &mut rng);
+        assert_eq!(path.input_text(), "closure i");
+
+        assert!(path.back());
+        assert_eq!(path.choices(), choices);
+        assert_eq!(path.input_text(), "closure ");
+        assert_eq!(true_choice(&path), "i");
+    }
+
+    #[test]
+    fn walking_back_to_the_start_uncommits() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 17);
+        let choices = path.choices().to_vec();
+        path.choose("b", &mut rng);
+        assert!(path.back());
+        assert_eq!(path.choices(), choices);
+        assert_eq!(path.phrase_index(), None);
+
+        path.choose("c", &mut rng);
+        assert_eq!(path.phrase_index(), Some(1));
+    }
+
+    #[test]
+    fn walking_back_finds_the_lost_path() {
+        let (mut path, mut rng) = start(&PHRASES, 2, 18);
+        stray(&mut path, &mut rng);
+        while path.info_type() != InfoType::Tedious {
+            let choice = path.choices()[0].clone();
+            path.choose(&choice, &mut rng);
+        }
+        while path.info_type() != InfoType::None {
+            assert!(path.back());
+        }
+        follow(&mut path, &mut rng);
+        assert_eq!(path.info_type(), InfoType::Fine);
+        assert_eq!(path.input_text(), "be healthy");
+    }
+
+    #[test]
+    fn choices_are_always_distinct() {
+        let phrases = ["aaaa aaaa", "bbbb bbbb", "Closure is used in Javascript"];
+        let decoys = DecoyKind::Trigram.build(&phrases, Granularity::Letter);
+        for seed in 0..32 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut path = ForkingPath::new(
+                &phrases,
+                MAX_BRANCHES,
+                Granularity::Letter,
+                decoys.clone(),
+                &mut rng,
+            );
+            while path.info_type() == InfoType::None {
+                let mut choices = path.choices().to_vec();
+                choices.sort_unstable();
+                choices.dedup();
+                assert_eq!(choices.len(), path.choices().len());
+
+                path.choose(&true_choice(&path), &mut rng);
+            }
+        }
+    }
+
+    #[test]
+    fn decoys_come_from_the_strategy() {
+        let mut rng = StdRng::seed_from_u64(20);
+        let phrases = ["Жить, не тужить", "жили-были"];
+        let decoys = DecoyKind::Frequency.build(&phrases, Granularity::Letter);
+        let mut path = ForkingPath::toward(&phrases, 0, 3, Granularity::Letter, decoys, &mut rng);
+        let alphabet = graphemes("житьнеуызбл");
+        while path.info_type() == InfoType::None {
+            assert!(path
+                .choices()
+                .iter()
+                .all(|choice| alphabet.contains(choice)));
+            path.choose(&true_choice(&path), &mut rng);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn needs_two_phrases() {
+        start(&["be healthy"], 2, 7);
+    }
+
+    #[test]
+    fn follows_phrases_in_any_script() {
+        let (mut path, mut rng) = start(&["Жить, не тужить", "和平"], 2, 8);
+        path.choose("ж", &mut rng);
+        follow(&mut path, &mut rng);
+        assert_eq!(path.info_type(), InfoType::Fine);
+        assert_eq!(path.input_text(), "жить, не тужить");
+        assert_eq!(path.steps(), 12);
+    }
+
+    #[test]
+    fn offers_whole_graphemes() {
+        // "é" spelled as an "e" followed by a combining acute accent.
+        let (mut path, mut rng) = start(&["Cafe\u{301} noir", "thé"], 2, 9);
+        path.choose("c", &mut rng);
+        path.choose("a", &mut rng);
+        path.choose("f", &mut rng);
+        assert_eq!(true_choice(&path), "\u{e9}");
+    }
+
+    #[test]
+    fn matches_typed_text_in_any_case() {
+        let (path, _) = start(&["Straße", "İstanbul"], 2, 10);
+        assert_eq!(path.match_input("S"), InputMatch::Choice("s".to_string()));
+        assert_eq!(
+            path.match_input("İ"),
+            InputMatch::Choice("i\u{307}".to_string())
+        );
+        assert_eq!(
+            path.match_input("i"),
+            InputMatch::Choice("i\u{307}".to_string())
+        );
+        assert_eq!(path.match_input("x"), InputMatch::None);
+        assert_eq!(path.match_input(""), InputMatch::None);
+    }
+
+    #[test]
+    fn waits_for_more_input_while_several_choices_start_alike() {
+        let (path, _) = start(&["SSH", "ß"], 2, 11);
+        assert_eq!(path.match_input("s"), InputMatch::Choice("s".to_string()));
+
+        // The "st" ligature folds to "st", "ß" to "ss".
+        let (path, _) = start(&["ßa", "\u{fb06}"], 2, 11);
+        assert_eq!(path.match_input("s"), InputMatch::Partial);
+        assert_eq!(path.match_input("SS"), InputMatch::Choice("ß".to_string()));
+        assert_eq!(
+            path.match_input("st"),
+            InputMatch::Choice("\u{fb06}".to_string())
+        );
+    }
+
+    #[test]
+    fn offers_a_choice_per_branch() {
+        let phrases = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot"];
+        for branches in MIN_BRANCHES..=MAX_BRANCHES {
+            let (mut path, mut rng) = start(&phrases, branches, 12);
+            assert_eq!(path.branches(), branches);
+            assert_eq!(path.choices().len(), branches);
+
+            let choice = path.choices()[0].clone();
+            path.choose(&choice, &mut rng);
+            assert_eq!(path.choices().len(), branches);
+            let phrase = graphemes(phrases[path.phrase_index().unwrap()]);
+            assert_eq!(true_choice(&path), phrase[1]);
+        }
+    }
+
+    #[test]
+    fn clamps_the_branches() {
+        assert_eq!(start(&PHRASES, 1, 13).0.branches(), MIN_BRANCHES);
+        assert_eq!(start(&PHRASES, 99, 13).0.branches(), MAX_BRANCHES);
+    }
+
+    #[test]
+    fn first_fork_fills_up_with_decoys_when_short_of_phrases() {
+        let (mut path, mut rng) = start(&PHRASES, 4, 14);
+        assert_eq!(path.choices().len(), 4);
+        assert!(path.choices().iter().any(|choice| choice == "b"));
+        assert!(path.choices().iter().any(|choice| choice == "c"));
+
+        path.choose("b", &mut rng);
+        assert_eq!(path.choices().len(), 4);
+    }
+
+    #[test]
+    fn toward_is_committed_from_the_start() {
+        let mut rng = StdRng::seed_from_u64(15);
+        let mut path = ForkingPath::toward(
+            &PHRASES,
+            1,
+            3,
+            Granularity::Letter,
+            Decoys::default(),
+            &mut rng,
+        );
+        assert_eq!(path.phrase_index(), Some(1));
+        assert_eq!(path.choices().len(), 3);
+        assert_eq!(true_choice(&path), "c");
+
+        follow(&mut path, &mut rng);
+        assert_eq!(path.input_text(), "closure is used in javascript");
+    }
+
+    #[test]
+    fn word_forks_offer_whole_words() {
+        let mut rng = StdRng::seed_from_u64(16);
+        let decoys = DecoyKind::Frequency.build(&PHRASES, Granularity::Word);
+        let mut path = ForkingPath::toward(&PHRASES, 1, 3, Granularity::Word, decoys, &mut rng);
+        assert_eq!(true_choice(&path), "closure");
+        assert!(path.choices().iter().all(|choice| is_inputable(choice)));
+
+        follow(&mut path, &mut rng);
+        assert_eq!(path.input_text(), "closure is used in javascript");
+        assert_eq!(path.forks(), Some(5));
+    }
+
+    #[test]
+    fn clause_forks_fill_in_the_punctuation() {
+        let phrases = ["Closure is,\nused in Javascript!", "be healthy"];
+        let mut rng = StdRng::seed_from_u64(17);
+        let decoys = DecoyKind::Frequency.build(&phrases, Granularity::Clause);
+        let mut path = ForkingPath::toward(&phrases, 0, 2, Granularity::Clause, decoys, &mut rng);
+        assert_eq!(true_choice(&path), "closure is");
+
+        follow(&mut path, &mut rng);
+        assert_eq!(path.info_type(), InfoType::Fine);
+        assert_eq!(path.input_text(), "closure is,\nused in javascript!");
+        assert_eq!(path.forks(), Some(2));
+    }
+
+    #[test]
+    fn phrases_starting_alike_stay_alive_until_they_part() {
+        let phrases = ["be healthy", "be wealthy", "find a good job"];
+        let (mut path, mut rng) = start(&phrases, 2, 18);
+        path.choose("b", &mut rng);
+        path.choose("e", &mut rng);
+        assert_eq!(path.input_text(), "be ");
+        assert_eq!(path.phrase_index(), None);
+        let mut choices = path.choices().to_vec();
+        choices.sort_unstable();
+        assert_eq!(choices, ["h", "w"]);
+
+        path.choose("w", &mut rng);
+        assert_eq!(path.phrase_index(), Some(1));
+        follow(&mut path, &mut rng);
+        assert_eq!(path.input_text(), "be wealthy");
+        assert_eq!(path.phrase_index(), Some(1));
+    }
+
+    #[test]
+    fn leaves_out_phrases_that_cannot_be_revealed() {
+        let phrases = ["Be healthy", "Be!", "...", "be, healthy", "find"];
+        assert_eq!(
+            ForkingPath::revealable(&phrases, Granularity::Letter),
+            [0, 4]
+        );
+        assert_eq!(
+            ForkingPath::revealable(&["Bee", "Be"], Granularity::Word),
+            [0, 1]
+        );
+
+        let (mut path, mut rng) = start(&phrases, 2, 19);
+        path.choose("b", &mut rng);
+        assert_eq!(path.choose("e", &mut rng), Outcome::Chosen(InfoType::None));
+        follow(&mut path, &mut rng);
+        assert_eq!(path.input_text(), "be healthy");
+        assert_eq!(path.phrase_index(), Some(0));
+    }
+
+    #[test]
+    fn shows_only_the_punctuation_every_possible_phrase_shares() {
+        let (mut path, mut rng) = start(&["Be, healthy", "Be wealthy!", "find"], 2, 21);
+        path.choose("b", &mut rng);
+        path.choose("e", &mut rng);
+        assert_eq!(path.input_text(), "be ");
+        path.choose("h", &mut rng);
+        assert_eq!(path.input_text(), "be, h");
+    }
+
+    #[test]
+    fn hints_agree_while_several_phrases_are_possible() {
+        let phrases = ["be healthy", "be wealthy", "be happy", "find a good job"];
+        for seed in 0..20 {
+            let (mut path, mut rng) = start(&phrases, 2, seed);
+            path.choose("b", &mut rng);
+            path.choose("e", &mut rng);
+            // Three phrases go on, and only two of them can be offered.
+            let choice = path.hint_choice().unwrap().to_string();
+            let rest = path.rest().unwrap();
+            assert!(rest.starts_with(&choice), "{}: {} {}", seed, choice, rest);
+            assert_eq!(
+                path.choose(&choice, &mut rng),
+                Outcome::Chosen(InfoType::None)
+            );
+        }
+    }
+
+    #[test]
+    fn hints_follow_the_phrase_offered() {
+        let phrases = ["be healthy", "be wealthy", "find a good job"];
+        let (mut path, mut rng) = start(&phrases, 2, 20);
+        path.choose("b", &mut rng);
+        path.choose("e", &mut rng);
+        assert_eq!(path.hint_choice(), Some("h"));
+        assert_eq!(path.rest().as_deref(), Some("healthy"));
+
+        path.choose("w", &mut rng);
+        assert_eq!(path.hint_choice(), Some("e"));
+        assert_eq!(path.rest().as_deref(), Some("ealthy"));
+
+        let decoy = decoy_choice(&path).unwrap();
+        path.choose(&decoy, &mut rng);
+        assert_eq!(path.hint_choice(), None);
+        assert_eq!(path.rest(), None);
+    }
+}
@@ -0,0 +1,178 @@
+use super::decoy::DecoyKind;
+use super::path::{Granularity, MAX_BRANCHES, MIN_BRANCHES};
+use super::storage;
+use super::theme::DEFAULT_THEME;
+use super::timer::Difficulty;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const PREFERENCES_FILE: &str = "preferences.ron";
+
+/// The text sizes to pick from, as factors of the default ones.
+pub const TEXT_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+pub const MAX_VOLUME: u8 = 10;
+/// The smallest text size in accessibility mode, whatever the text size
+/// picked.
+pub const MIN_ACCESSIBLE_FONT_SIZE: f32 = 20.0;
+
+/// The player's choices on the settings and splash screens, kept across
+/// sessions and applied at startup.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// One of [`TEXT_SCALES`].
+    pub text_scale: f32,
+    /// The name of the theme picked.
+    pub theme: String,
+    /// Accessibility mode: a high-contrast palette over the theme, text no
+    /// smaller than [`MIN_ACCESSIBLE_FONT_SIZE`] and choices marked by more
+    /// than their color.
+    pub accessible: bool,
+    /// Sets every text in a font easier to read with dyslexia, where the
+    /// locale's script allows.
+    pub dyslexic_font: bool,
+    pub branches: usize,
+    pub difficulty: Difficulty,
+    pub granularity: Granularity,
+    pub decoys: DecoyKind,
+    /// From 0, muted, to [`MAX_VOLUME`]. Kept for the sounds to come, as the
+    /// game makes none yet.
+    pub volume: u8,
+    /// The locale picked, or `None` to follow the system.
+    pub language: Option<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            text_scale: 1.0,
+            theme: DEFAULT_THEME.to_string(),
+            accessible: false,
+            dyslexic_font: false,
+            branches: MIN_BRANCHES,
+            difficulty: Difficulty::default(),
+            granularity: Granularity::default(),
+            decoys: DecoyKind::default(),
+            volume: MAX_VOLUME,
+            language: None,
+        }
+    }
+}
+
+impl Preferences {
+    /// Loads the saved preferences, or the default ones if there are none
+    /// yet.
+    pub fn load() -> Self {
+        let text = match storage::load(PREFERENCES_FILE) {
+            Some(text) => text,
+            None => return Self::default(),
+        };
+        Self::parse(&text).unwrap_or_else(|error| {
+            warn!(
+                "Could not read the preferences, using the defaults: {}",
+                error
+            );
+            Self::default()
+        })
+    }
+
+    /// Parses saved preferences, bringing values out of range back in, e.g.
+    /// from a file edited by hand.
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut preferences: Self = ron::de::from_str(text)?;
+        if !TEXT_SCALES.contains(&preferences.text_scale) {
+            preferences.text_scale = 1.0;
+        }
+        preferences.branches = preferences.branches.clamp(MIN_BRANCHES, MAX_BRANCHES);
+        preferences.volume = preferences.volume.min(MAX_VOLUME);
+        Ok(preferences)
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        storage::save(PREFERENCES_FILE, &text)
+    }
+
+    /// `size` scaled to the text size picked, and raised to the minimum of
+    /// accessibility mode.
+    pub fn font_size(&self, size: f32) -> f32 {
+        let size = size * self.text_scale;
+        if self.accessible {
+            size.max(MIN_ACCESSIBLE_FONT_SIZE)
+        } else {
+            size
+        }
+    }
+
+    /// Moves the text size a step up, or down if `up` is false, staying
+    /// within [`TEXT_SCALES`].
+    pub fn step_text_scale(&mut self, up: bool) {
+        let index = TEXT_SCALES
+            .iter()
+            .position(|&scale| scale == self.text_scale)
+            .unwrap_or(1);
+        let index = if up {
+            (index + 1).min(TEXT_SCALES.len() - 1)
+        } else {
+            index.saturating_sub(1)
+        };
+        self.text_scale = TEXT_SCALES[index];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_ron() {
+        let preferences = Preferences {
+            text_scale: 1.25,
+            theme: "parchment".to_string(),
+            accessible: true,
+            dyslexic_font: true,
+            branches: 4,
+            difficulty: Difficulty::Hard,
+            granularity: Granularity::Clause,
+            decoys: DecoyKind::Bigram,
+            volume: 3,
+            language: Some("de-DE".to_string()),
+        };
+        let text = ron::ser::to_string(&preferences).unwrap();
+        assert_eq!(Preferences::parse(&text).unwrap(), preferences);
+    }
+
+    #[test]
+    fn fills_in_and_fixes_what_the_file_gets_wrong() {
+        let preferences = Preferences::parse("(text_scale: 3.0, branches: 99)").unwrap();
+        assert_eq!(preferences.text_scale, 1.0);
+        assert_eq!(preferences.branches, MAX_BRANCHES);
+        assert_eq!(preferences.theme, DEFAULT_THEME);
+        assert_eq!(preferences.volume, MAX_VOLUME);
+        assert_eq!(preferences.decoys, DecoyKind::Trigram);
+    }
+
+    #[test]
+    fn steps_text_size_within_range() {
+        let mut preferences = Preferences::default();
+        preferences.step_text_scale(false);
+        preferences.step_text_scale(false);
+        assert_eq!(preferences.text_scale, 0.75);
+        for _ in 0..5 {
+            preferences.step_text_scale(true);
+        }
+        assert_eq!(preferences.text_scale, 1.5);
+    }
+
+    #[test]
+    fn keeps_text_large_enough_in_accessibility_mode() {
+        let mut preferences = Preferences {
+            text_scale: 0.75,
+            ..Default::default()
+        };
+        assert_eq!(preferences.font_size(16.0), 12.0);
+        preferences.accessible = true;
+        assert_eq!(preferences.font_size(16.0), MIN_ACCESSIBLE_FONT_SIZE);
+        assert_eq!(preferences.font_size(80.0), 60.0);
+    }
+}
@@ -0,0 +1,249 @@
+use super::decoy::DecoyKind;
+use super::game::GameMode;
+use super::path::Granularity;
+use super::storage;
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the format, or the rules a replay is resimulated with,
+/// change in a way older replays would not play back the same.
+pub const REPLAY_VERSION: u32 = 6;
+
+/// Where the latest run is always saved.
+pub const LAST_REPLAY: &str = "replays/last.replay.ron";
+
+/// How many runs are kept under the time they were saved at, besides the
+/// last one. Older ones are deleted as new ones come in.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+const MAX_REPLAYS: usize = 20;
+
+/// Everything needed to walk a run again, fork by fork.
+///
+/// The phrases are stored rather than looked up again, so that a replay
+/// still plays back after its pack was edited; the pack name and version are
+/// kept to tell which pack it was recorded with.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub branches: usize,
+    pub granularity: Granularity,
+    pub decoys: DecoyKind,
+    pub pack: String,
+    pub pack_version: u32,
+    pub phrases: Vec<String>,
+    pub steps: Vec<ReplayStep>,
+}
+
+/// One fork of a run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReplayStep {
+    /// The choices offered, in the order they were shown.
+    pub choices: Vec<String>,
+    pub action: ReplayAction,
+    /// Seconds since the run started.
+    pub time: f32,
+}
+
+/// What the player did at a fork.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum ReplayAction {
+    Choose(String),
+    /// Walked back to the fork before.
+    Back,
+}
+
+/// Just enough of a replay to tell its version, whatever else it holds.
+#[derive(Deserialize)]
+struct ReplayHeader {
+    version: u32,
+}
+
+impl Replay {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let header = ron::de::from_str::<ReplayHeader>(text)?;
+        if header.version != REPLAY_VERSION {
+            anyhow::bail!(
+                "replay version {} is not supported, expected {}",
+                header.version,
+                REPLAY_VERSION
+            );
+        }
+        Ok(ron::de::from_str(text)?)
+    }
+
+    pub fn to_ron(&self) -> anyhow::Result<String> {
+        Ok(ron::ser::to_string_pretty(
+            self,
+            ron::ser::PrettyConfig::default(),
+        )?)
+    }
+
+    pub fn load(name: &str) -> anyhow::Result<Self> {
+        let text =
+            storage::load(name).ok_or_else(|| anyhow::anyhow!("no replay saved as {}", name))?;
+        Self::parse(&text)
+    }
+
+    /// Saves the replay as the last one, and on desktop also under the time
+    /// it was saved at so that the latest [`MAX_REPLAYS`] runs are kept
+    /// around.
+    pub fn save(&self) -> anyhow::Result<()> {
+        let text = self.to_ron()?;
+        storage::save(LAST_REPLAY, &text)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let saved = storage::list("replays")
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            storage::save(&replay_name(super::utils::unix_time(), &saved), &text)?;
+            for name in stale_replays(storage::list("replays"), MAX_REPLAYS) {
+                storage::remove(&name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the replay to watch from `--replay <path>` on desktop.
+pub fn requested_replay() -> Option<Replay> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = replay_path_from_args(std::env::args().skip(1))?;
+        let text = std::fs::read_to_string(&path).ok()?;
+        Replay::parse(&text).ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn replay_path_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--replay" {
+            return args.next();
+        }
+        if let Some(path) = arg.strip_prefix("--replay=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// The name to save a replay under at `time`, counting up past the names
+/// already `saved` when several runs end within the same second.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn replay_name(time: u64, saved: &[String]) -> String {
+    (0..)
+        .map(|count| match count {
+            0 => format!("replays/{}.replay.ron", time),
+            _ => format!("replays/{}-{}.replay.ron", time, count),
+        })
+        .find(|name| !saved.contains(name))
+        .unwrap()
+}
+
+/// Picks the replays beyond the `keep` most recently written, leaving the
+/// last one and files that are not replays alone.
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn stale_replays<T: Ord>(mut saved: Vec<(String, T)>, keep: usize) -> Vec<String> {
+    saved.retain(|(name, _)| name != LAST_REPLAY && name.ends_with(".replay.ron"));
+    saved.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    saved.into_iter().skip(keep).map(|(name, _)| name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed: 42,
+            mode: GameMode::Random,
+            branches: 2,
+            granularity: Granularity::Word,
+            decoys: DecoyKind::Trigram,
+            pack: "Default".to_string(),
+            pack_version: 1,
+            phrases: vec!["be healthy".to_string(), "be wealthy".to_string()],
+            steps: vec![
+                ReplayStep {
+                    choices: vec!["b".to_string(), "q".to_string()],
+                    action: ReplayAction::Choose("b".to_string()),
+                    time: 1.5,
+                },
+                ReplayStep {
+                    choices: vec!["e".to_string(), "x".to_string()],
+                    action: ReplayAction::Back,
+                    time: 2.0,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_ron() {
+        let replay = replay();
+        assert_eq!(Replay::parse(&replay.to_ron().unwrap()).unwrap(), replay);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let text = Replay {
+            version: REPLAY_VERSION + 1,
+            ..replay()
+        }
+        .to_ron()
+        .unwrap();
+        let error = Replay::parse(&text).unwrap_err();
+        assert!(error.to_string().contains("not supported"));
+    }
+
+    #[test]
+    fn reads_the_replay_path_from_args() {
+        let args = |line: &str| {
+            line.split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            replay_path_from_args(args("--replay run.replay.ron")),
+            Some("run.replay.ron".to_string())
+        );
+        assert_eq!(
+            replay_path_from_args(args("--seed 7 --replay=a.ron")),
+            Some("a.ron".to_string())
+        );
+        assert_eq!(replay_path_from_args(args("--seed 7")), None);
+    }
+
+    #[test]
+    fn keeps_the_latest_replays() {
+        let saved = vec![
+            ("replays/100.replay.ron".to_string(), 100),
+            (LAST_REPLAY.to_string(), 10),
+            ("replays/300.replay.ron".to_string(), 300),
+            ("replays/notes.txt".to_string(), 1),
+            ("replays/200.replay.ron".to_string(), 200),
+            ("replays/50.replay.ron".to_string(), 50),
+        ];
+        assert_eq!(
+            stale_replays(saved.clone(), 2),
+            vec!["replays/100.replay.ron", "replays/50.replay.ron"]
+        );
+        assert!(stale_replays(saved, 4).is_empty());
+    }
+
+    #[test]
+    fn names_replays_saved_within_a_second_apart() {
+        assert_eq!(replay_name(100, &[]), "replays/100.replay.ron");
+        let saved = ["replays/100.replay.ron", "replays/100-1.replay.ron"].map(String::from);
+        assert_eq!(replay_name(100, &saved), "replays/100-2.replay.ron");
+        assert_eq!(replay_name(101, &saved), "replays/101.replay.ron");
+    }
+}
@@ -0,0 +1,298 @@
+use super::decoy::DecoyKind;
+use super::path::{Granularity, MAX_BRANCHES, MIN_BRANCHES};
+use bevy::log::warn;
+use rand::{Error, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+
+const GRANULARITY_NAMES: [(Granularity, &str); 3] = [
+    (Granularity::Letter, "letter"),
+    (Granularity::Word, "word"),
+    (Granularity::Clause, "clause"),
+];
+const DECOY_NAMES: [(DecoyKind, &str); 4] = [
+    (DecoyKind::Uniform, "uniform"),
+    (DecoyKind::Frequency, "frequency"),
+    (DecoyKind::Bigram, "bigram"),
+    (DecoyKind::Trigram, "trigram"),
+];
+
+/// Everything the forks of a run are drawn from: its seed, and the settings
+/// that change what the seed gives. Shared as one code, such as
+/// `42-3-word-trigram`, so that the same forks can be played again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunCode {
+    pub seed: u64,
+    pub branches: usize,
+    pub granularity: Granularity,
+    pub decoys: DecoyKind,
+}
+
+impl fmt::Display for RunCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}-{}",
+            self.seed,
+            self.branches,
+            name_of(&GRANULARITY_NAMES, self.granularity),
+            name_of(&DECOY_NAMES, self.decoys)
+        )
+    }
+}
+
+impl FromStr for RunCode {
+    type Err = anyhow::Error;
+
+    fn from_str(code: &str) -> anyhow::Result<Self> {
+        let mut parts = code.trim().split('-');
+        let seed = parts.next().and_then(|seed| seed.parse().ok());
+        let branches = parts
+            .next()
+            .and_then(|branches| branches.parse().ok())
+            .filter(|branches| (MIN_BRANCHES..=MAX_BRANCHES).contains(branches));
+        let granularity = named(&GRANULARITY_NAMES, parts.next());
+        let decoys = named(&DECOY_NAMES, parts.next());
+        match (seed, branches, granularity, decoys, parts.next()) {
+            (Some(seed), Some(branches), Some(granularity), Some(decoys), None) => Ok(Self {
+                seed,
+                branches,
+                granularity,
+                decoys,
+            }),
+            _ => anyhow::bail!(
+                "{} is no run code such as 42-3-word-trigram: a seed, the choices per fork \
+                 from {} to {}, what forks offer and the decoys",
+                code,
+                MIN_BRANCHES,
+                MAX_BRANCHES
+            ),
+        }
+    }
+}
+
+fn name_of<T: PartialEq>(names: &[(T, &'static str)], value: T) -> &'static str {
+    names
+        .iter()
+        .find(|(named, _)| *named == value)
+        .map_or("", |(_, name)| name)
+}
+
+fn named<T: Copy>(names: &[(T, &str)], name: Option<&str>) -> Option<T> {
+    let name = name?;
+    names
+        .iter()
+        .find(|(_, named)| named.eq_ignore_ascii_case(name))
+        .map(|(value, _)| *value)
+}
+
+/// The randomness of a run, reproducible from its [`RunCode`].
+///
+/// Every phrase pick and decoy letter of a run is drawn from here, so
+/// playing the same code with the same choices walks the same forks.
+pub struct GameRng {
+    /// A run asked for at launch, played by the first run.
+    requested: Option<RunCode>,
+    rng: ChaCha8Rng,
+    code: RunCode,
+}
+
+impl GameRng {
+    pub fn new(requested: Option<RunCode>) -> Self {
+        Self {
+            requested,
+            rng: ChaCha8Rng::seed_from_u64(0),
+            code: RunCode {
+                seed: 0,
+                branches: MIN_BRANCHES,
+                granularity: Granularity::Letter,
+                decoys: DecoyKind::Trigram,
+            },
+        }
+    }
+
+    /// Reseeds for a new run played with `branches`, `granularity` and
+    /// `decoys`, unless the run requested at launch is still unplayed, whose
+    /// code says how it is played instead. Returns the code of the run.
+    pub fn start_run(
+        &mut self,
+        branches: usize,
+        granularity: Granularity,
+        decoys: DecoyKind,
+    ) -> RunCode {
+        let code = self.requested.take().unwrap_or_else(|| RunCode {
+            seed: rand::random(),
+            branches,
+            granularity,
+            decoys,
+        });
+        self.start_code(code);
+        code
+    }
+
+    /// Reseeds for the run of `code`.
+    pub fn start_code(&mut self, code: RunCode) {
+        self.code = code;
+        self.rng = ChaCha8Rng::seed_from_u64(code.seed);
+    }
+
+    /// The code of the current, or last, run.
+    pub fn code(&self) -> RunCode {
+        self.code
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Reads the run to play from `--seed <code>` on desktop, or from the
+/// `seed` query parameter of the page on the web.
+pub fn requested_code() -> Option<RunCode> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let code = code_from_args(std::env::args().skip(1))?;
+    #[cfg(target_arch = "wasm32")]
+    let code = {
+        let search = web_sys::window()?.location().search().ok()?;
+        code_from_query(&search)?
+    };
+    code.parse()
+        .map_err(|error| warn!("Not playing the run asked for: {}", error))
+        .ok()
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+fn code_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            return args.next();
+        }
+        if let Some(code) = arg.strip_prefix("--seed=") {
+            return Some(code.to_string());
+        }
+    }
+    None
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+fn code_from_query(search: &str) -> Option<String> {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seed="))
+        .filter(|code| !code.is_empty())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::ForkingPath;
+
+    const PHRASES: [&str; 4] = [
+        "be healthy",
+        "be wealthy",
+        "find a good job",
+        "happy new year",
+    ];
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn code(seed: u64) -> RunCode {
+        RunCode {
+            seed,
+            branches: 3,
+            granularity: Granularity::Word,
+            decoys: DecoyKind::Trigram,
+        }
+    }
+
+    #[test]
+    fn reads_the_code_from_args() {
+        let code = |line| code_from_args(args(line));
+        assert_eq!(
+            code("--seed 42-3-word-trigram").unwrap(),
+            "42-3-word-trigram"
+        );
+        assert_eq!(code("--fullscreen --seed=7").unwrap(), "7");
+        assert_eq!(code("--seed"), None);
+        assert_eq!(code(""), None);
+    }
+
+    #[test]
+    fn reads_the_code_from_the_query() {
+        assert_eq!(code_from_query("?seed=42").unwrap(), "42");
+        assert_eq!(
+            code_from_query("?lang=de&seed=7-2-letter-bigram").unwrap(),
+            "7-2-letter-bigram"
+        );
+        assert_eq!(code_from_query("?seed="), None);
+        assert_eq!(code_from_query(""), None);
+    }
+
+    #[test]
+    fn codes_round_trip_through_text() {
+        assert_eq!(code(42).to_string(), "42-3-word-trigram");
+        assert_eq!("42-3-word-trigram".parse::<RunCode>().unwrap(), code(42));
+        assert_eq!("42-3-Word-TRIGRAM".parse::<RunCode>().unwrap(), code(42));
+        for text in [
+            "42",
+            "42-3-word",
+            "42-9-word-trigram",
+            "x-3-word-trigram",
+            "42-3-word-trigram-1",
+        ] {
+            assert!(text.parse::<RunCode>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn plays_the_requested_run_first() {
+        let mut rng = GameRng::new(Some(code(42)));
+        let first = rng.start_run(2, Granularity::Letter, DecoyKind::Uniform);
+        assert_eq!(first, code(42));
+        assert_eq!(rng.code(), code(42));
+        let second = rng.start_run(2, Granularity::Letter, DecoyKind::Uniform);
+        assert_ne!(second.seed, 42);
+        assert_eq!(second.branches, 2);
+        assert_eq!(second.decoys, DecoyKind::Uniform);
+    }
+
+    #[test]
+    fn same_code_walks_the_same_forks() {
+        let walk = |seed| {
+            let mut rng = GameRng::new(Some(code(seed)));
+            let code = rng.start_run(2, Granularity::Letter, DecoyKind::Uniform);
+            let decoys = code.decoys.build(&PHRASES, code.granularity);
+            let mut path =
+                ForkingPath::new(&PHRASES, code.branches, code.granularity, decoys, &mut rng);
+            let mut forks = vec![path.choices().to_vec()];
+            for _ in 0..8 {
+                let choice = path.choices()[0].clone();
+                path.choose(&choice, &mut rng);
+                forks.push(path.choices().to_vec());
+            }
+            forks
+        };
+        assert_eq!(walk(42), walk(42));
+        assert_ne!(walk(42), walk(43));
+    }
+}
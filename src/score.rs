@@ -0,0 +1,138 @@
+use super::hint::Hint;
+use super::path::InfoType;
+
+/// Points for taking the true choice at every fork, before the time bonus.
+const BASE_POINTS: f32 = 1000.0;
+const WRONG_FORK_PENALTY: u32 = 50;
+const BACKTRACK_PENALTY: u32 = 20;
+/// Seconds a fork may take before the time bonus runs out.
+const PAR_SECONDS_PER_FORK: f32 = 2.0;
+const POINTS_PER_SECOND_SAVED: f32 = 10.0;
+
+/// How a run went, tallied as it is played.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScoreCard {
+    /// How many forks the revealed phrase needs, without a wrong turn.
+    pub forks: usize,
+    /// Choices taken and steps walked back.
+    pub steps: usize,
+    /// How many times the path strayed from every phrase.
+    pub wrong_forks: usize,
+    pub backtracks: usize,
+    pub hints: usize,
+    /// The points the hints taken cost, summed up.
+    pub hint_cost: u32,
+    /// Seconds from the first fork to the reveal.
+    pub seconds: f32,
+}
+
+impl ScoreCard {
+    /// Tallies a choice that left the path in `after`, from `before`.
+    pub fn record_choice(&mut self, before: InfoType, after: InfoType) {
+        self.steps += 1;
+        if before == InfoType::None && after == InfoType::Neverending {
+            self.wrong_forks += 1;
+        }
+    }
+
+    pub fn record_back(&mut self) {
+        self.steps += 1;
+        self.backtracks += 1;
+    }
+
+    pub fn record_hint(&mut self, hint: Hint) {
+        self.hints += 1;
+        self.hint_cost += hint.cost();
+    }
+
+    /// Closes the card once the phrase of `forks` forks is revealed.
+    pub fn finish(&mut self, forks: usize, seconds: f32) {
+        self.forks = forks;
+        self.seconds = seconds;
+    }
+
+    /// The final score.
+    ///
+    /// A run taking the true choice at every fork earns [`BASE_POINTS`], cut
+    /// in proportion to the extra steps taken. Fast runs earn a bonus, and
+    /// every wrong turn and step back costs a fixed penalty, every hint its
+    /// own cost.
+    pub fn score(&self) -> u32 {
+        if self.steps == 0 {
+            return 0;
+        }
+        let efficiency = self.forks as f32 / self.steps as f32;
+        let par = self.forks as f32 * PAR_SECONDS_PER_FORK;
+        let bonus = (par - self.seconds).max(0.0) * POINTS_PER_SECOND_SAVED;
+        let penalty = self.wrong_forks as u32 * WRONG_FORK_PENALTY
+            + self.backtracks as u32 * BACKTRACK_PENALTY
+            + self.hint_cost;
+        ((BASE_POINTS * efficiency + bonus).round() as u32).saturating_sub(penalty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn perfect_run(forks: usize, seconds: f32) -> ScoreCard {
+        let mut card = ScoreCard::default();
+        for _ in 0..forks {
+            card.record_choice(InfoType::None, InfoType::None);
+        }
+        card.finish(forks, seconds);
+        card
+    }
+
+    #[test]
+    fn perfect_slow_run_scores_the_base_points() {
+        assert_eq!(perfect_run(9, 60.0).score(), 1000);
+    }
+
+    #[test]
+    fn fast_runs_earn_a_bonus() {
+        assert_eq!(perfect_run(9, 8.0).score(), 1100);
+    }
+
+    #[test]
+    fn mistakes_cost_points() {
+        let mut card = ScoreCard::default();
+        card.record_choice(InfoType::None, InfoType::None);
+        card.record_choice(InfoType::None, InfoType::Neverending);
+        card.record_choice(InfoType::Neverending, InfoType::Neverending);
+        card.record_back();
+        card.record_back();
+        for _ in 0..8 {
+            card.record_choice(InfoType::None, InfoType::None);
+        }
+        card.finish(9, 60.0);
+
+        assert_eq!(card.steps, 13);
+        assert_eq!(card.wrong_forks, 1);
+        assert_eq!(card.backtracks, 2);
+        // 1000 * 9 / 13, less 50 for the wrong turn and 2 * 20 for walking back.
+        assert_eq!(card.score(), 692 - 90);
+        assert!(card.score() < perfect_run(9, 60.0).score());
+    }
+
+    #[test]
+    fn hints_cost_points() {
+        let mut card = perfect_run(9, 60.0);
+        card.record_hint(Hint::Flash);
+        card.record_hint(Hint::Word);
+        assert_eq!(card.hints, 2);
+        assert_eq!(card.score(), 1000 - 25 - 100);
+    }
+
+    #[test]
+    fn never_scores_below_zero() {
+        let mut card = ScoreCard::default();
+        for _ in 0..40 {
+            card.record_choice(InfoType::None, InfoType::Neverending);
+            card.record_back();
+        }
+        card.record_choice(InfoType::None, InfoType::None);
+        card.finish(1, 60.0);
+        assert_eq!(card.score(), 0);
+    }
+}
@@ -0,0 +1,519 @@
+use super::announce::{changed_lines, Announcement};
+use super::controls::{button_name, key_name, spawn_corner_buttons, Action, Bindings};
+use super::game::GameConfig;
+use super::locale::{LocaleManifest, Localization};
+use super::path::{MAX_BRANCHES, MIN_BRANCHES};
+use super::preferences::{Preferences, MAX_VOLUME};
+use super::theme::{theme_names, FontRole, Theme};
+use super::{despawn_screen, GameState};
+use bevy::prelude::*;
+
+/// How many lines of the menu are shown at once, so that it fits the window.
+const VISIBLE_ROWS: usize = 16;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Preferences::load())
+            .init_resource::<SettingsMenu>()
+            .add_startup_system(apply_preferences)
+            .add_system(remember_preferences.label("remember_preferences"))
+            .add_system(save_preferences.after("remember_preferences"))
+            .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(settings_setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(navigate.label("navigate"))
+                    .with_system(display.after("navigate")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(GameState::Settings)
+                    .with_system(save_bindings)
+                    .with_system(despawn_screen::<OnSettingsScreen>),
+            );
+    }
+}
+
+#[derive(Component)]
+struct OnSettingsScreen;
+
+#[derive(Component)]
+struct TextTitle;
+
+#[derive(Component)]
+struct TextRows;
+
+#[derive(Component)]
+struct TextControls;
+
+/// A line of the settings screen: a preference, then the binding of every
+/// action.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Row {
+    TextSize,
+    Theme,
+    Accessible,
+    DyslexicFont,
+    Branches,
+    Difficulty,
+    Decoys,
+    Volume,
+    Language,
+    Binding(Action),
+}
+
+impl Row {
+    fn all() -> Vec<Row> {
+        let mut rows = vec![
+            Row::TextSize,
+            Row::Theme,
+            Row::Accessible,
+            Row::DyslexicFont,
+            Row::Branches,
+            Row::Difficulty,
+            Row::Decoys,
+            Row::Volume,
+            Row::Language,
+        ];
+        rows.extend(Action::all().into_iter().map(Row::Binding));
+        rows
+    }
+}
+
+/// Where the player is in the menu.
+#[derive(Default)]
+struct SettingsMenu {
+    selected: usize,
+    /// Set while waiting for the key or button to bind to the selected
+    /// action.
+    waiting: bool,
+    /// The selected line as last read out to screen readers.
+    announced: String,
+}
+
+fn settings_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+    mut menu: ResMut<SettingsMenu>,
+) {
+    *menu = SettingsMenu::default();
+
+    // Fonts, sizes and colors are set by `display`, so that changing them
+    // shows at once.
+    let style = TextStyle::default();
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                margin: Rect::all(Val::Auto),
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(OnSettingsScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect {
+                            bottom: Val::Px(theme.margins.title),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: Text::with_section("", style.clone(), Default::default()),
+                    ..Default::default()
+                })
+                .insert(TextTitle);
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section("", style.clone(), Default::default()),
+                    ..Default::default()
+                })
+                .insert(TextRows);
+        });
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position: Rect {
+                    bottom: Val::Px(theme.margins.footer),
+                    left: Val::Px(theme.margins.footer),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            text: Text::with_section("", style, Default::default()),
+            ..Default::default()
+        })
+        .insert(OnSettingsScreen)
+        .insert(TextControls);
+
+    let style = theme.text_style(
+        FontRole::Body,
+        theme.sizes.info,
+        theme.palette.text,
+        &asset_server,
+        &localization,
+        &preferences,
+    );
+    let buttons = spawn_corner_buttons(
+        &mut commands,
+        style,
+        &theme,
+        &localization,
+        &[("button-back", Action::Back)],
+    );
+    commands.entity(buttons).insert(OnSettingsScreen);
+}
+
+/// The localized name of `action`.
+fn action_name(localization: &Localization, action: Action) -> String {
+    match action {
+        Action::ChooseSlot(slot) => {
+            let slot = (slot + 1).to_string();
+            localization.format(action.key(), &[("slot", slot.as_str())])
+        }
+        _ => localization.get(action.key()).to_string(),
+    }
+}
+
+/// Moves through the menu, changes preferences and binds keys and gamepad
+/// buttons to actions.
+///
+/// Reacts to keys as they are pressed rather than released, so that the key
+/// just bound is not taken for its new action when let go.
+fn navigate(
+    mut menu: ResMut<SettingsMenu>,
+    mut bindings: ResMut<Bindings>,
+    mut preferences: ResMut<Preferences>,
+    mut config: ResMut<GameConfig>,
+    mut localization: ResMut<Localization>,
+    manifests: Res<Assets<LocaleManifest>>,
+    themes: Res<Assets<Theme>>,
+    mut game_state: ResMut<State<GameState>>,
+    actions: Res<Input<Action>>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+) {
+    let rows = Row::all();
+    let selected = rows[menu.selected];
+
+    if menu.waiting {
+        let action = match selected {
+            Row::Binding(action) => action,
+            _ => return,
+        };
+        // Esc and Select always cancel, so that a player can never get stuck
+        // here.
+        if let Some(&key) = keys.get_just_pressed().next() {
+            if key != KeyCode::Escape {
+                bindings.bind(action, key);
+            }
+            menu.waiting = false;
+        } else if let Some(&GamepadButton(_, button)) = buttons.get_just_pressed().next() {
+            if button != GamepadButtonType::Select {
+                bindings.bind_button(action, button);
+            }
+            menu.waiting = false;
+        }
+        return;
+    }
+
+    if actions.just_pressed(Action::Back) {
+        game_state.set(GameState::Splash).unwrap();
+        return;
+    } else if actions.just_pressed(Action::Up) {
+        menu.selected = (menu.selected + rows.len() - 1) % rows.len();
+        return;
+    } else if actions.just_pressed(Action::Down) {
+        menu.selected = (menu.selected + 1) % rows.len();
+        return;
+    }
+
+    // Confirm steps preferences up like Right, for gamepads and touch.
+    let up = actions.just_pressed(Action::Right) || actions.just_pressed(Action::Confirm);
+    let down = actions.just_pressed(Action::Left);
+    if let Row::Binding(action) = selected {
+        if actions.just_pressed(Action::Confirm) {
+            menu.waiting = true;
+        } else if actions.just_pressed(Action::Undo) {
+            bindings.reset(action);
+        }
+        return;
+    }
+    if !up && !down {
+        return;
+    }
+    match selected {
+        Row::TextSize => preferences.step_text_scale(up),
+        Row::Theme => {
+            let names = theme_names(&themes);
+            if let Some(name) = step_name(&names, &preferences.theme, up) {
+                preferences.theme = name.to_string();
+            }
+        }
+        Row::Accessible => preferences.accessible = !preferences.accessible,
+        Row::DyslexicFont => preferences.dyslexic_font = !preferences.dyslexic_font,
+        Row::Branches if up => config.branches = (config.branches + 1).min(MAX_BRANCHES),
+        Row::Branches => config.branches = config.branches.saturating_sub(1).max(MIN_BRANCHES),
+        Row::Difficulty if up => {
+            config.difficulty = config.difficulty.harder().unwrap_or(config.difficulty)
+        }
+        Row::Difficulty => {
+            config.difficulty = config.difficulty.easier().unwrap_or(config.difficulty)
+        }
+        Row::Decoys => config.decoys = config.decoys.next(),
+        Row::Volume if up => preferences.volume = (preferences.volume + 1).min(MAX_VOLUME),
+        Row::Volume => preferences.volume = preferences.volume.saturating_sub(1),
+        Row::Language => {
+            localization.cycle_locale(&manifests);
+            preferences.language = Some(localization.requested().to_string());
+        }
+        Row::Binding(_) => {}
+    }
+}
+
+/// The name after `current` in `names`, or before it if `up` is false,
+/// wrapping around. Starts from the first name if `current` is not there.
+fn step_name<'a>(names: &'a [String], current: &str, up: bool) -> Option<&'a str> {
+    let index = match names.iter().position(|name| name == current) {
+        Some(index) if up => (index + 1) % names.len(),
+        Some(index) => (index + names.len() - 1) % names.len(),
+        None => 0,
+    };
+    names.get(index).map(String::as_str)
+}
+
+/// The value shown for `row`.
+fn row_value(
+    row: Row,
+    bindings: &Bindings,
+    preferences: &Preferences,
+    config: &GameConfig,
+    localization: &Localization,
+    manifests: &Assets<LocaleManifest>,
+) -> String {
+    match row {
+        Row::TextSize => format!("{}%", (preferences.text_scale * 100.0).round()),
+        Row::Theme => preferences.theme.clone(),
+        Row::Accessible => switch_value(preferences.accessible, localization),
+        Row::DyslexicFont => switch_value(preferences.dyslexic_font, localization),
+        Row::Branches => config.branches.to_string(),
+        Row::Difficulty => localization.get(config.difficulty.key()).to_string(),
+        Row::Decoys => localization.get(config.decoys.key()).to_string(),
+        Row::Volume => format!(
+            "{}%",
+            u32::from(preferences.volume) * 100 / u32::from(MAX_VOLUME)
+        ),
+        Row::Language => localization.locale_name(manifests).to_string(),
+        Row::Binding(action) => bindings
+            .keys(action)
+            .iter()
+            .map(|&key| key_name(key))
+            .chain(
+                bindings
+                    .buttons(action)
+                    .iter()
+                    .map(|&button| button_name(button)),
+            )
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn switch_value(on: bool, localization: &Localization) -> String {
+    let key = if on { "settings-on" } else { "settings-off" };
+    localization.get(key).to_string()
+}
+
+fn row_name(row: Row, localization: &Localization) -> String {
+    let key = match row {
+        Row::TextSize => "settings-text-size",
+        Row::Theme => "settings-theme",
+        Row::Accessible => "settings-accessible",
+        Row::DyslexicFont => "settings-dyslexic-font",
+        Row::Branches => "settings-branches",
+        Row::Difficulty => "settings-difficulty",
+        Row::Decoys => "settings-decoys",
+        Row::Volume => "settings-volume",
+        Row::Language => "settings-language",
+        Row::Binding(action) => return action_name(localization, action),
+    };
+    localization.get(key).to_string()
+}
+
+fn display(
+    mut menu: ResMut<SettingsMenu>,
+    bindings: Res<Bindings>,
+    preferences: Res<Preferences>,
+    config: Res<GameConfig>,
+    localization: Res<Localization>,
+    manifests: Res<Assets<LocaleManifest>>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+    mut announcements: EventWriter<Announcement>,
+    mut query: QuerySet<(
+        QueryState<&mut Text, With<TextTitle>>,
+        QueryState<&mut Text, With<TextRows>>,
+        QueryState<&mut Text, With<TextControls>>,
+    )>,
+) {
+    let rows = Row::all();
+    // Scroll so that the selected line stays in view.
+    let first = menu
+        .selected
+        .saturating_sub(VISIBLE_ROWS / 2)
+        .min(rows.len().saturating_sub(VISIBLE_ROWS));
+    let lines = rows
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(VISIBLE_ROWS)
+        .map(|(index, &row)| {
+            let marker = if index == menu.selected { ">" } else { " " };
+            let value = row_value(
+                row,
+                &bindings,
+                &preferences,
+                &config,
+                &localization,
+                &manifests,
+            );
+            format!("{} {}: {}", marker, row_name(row, &localization), value)
+        })
+        .collect::<Vec<_>>();
+
+    let controls = if menu.waiting {
+        let name = row_name(rows[menu.selected], &localization);
+        localization.format("settings-waiting", &[("action", name.as_str())])
+    } else {
+        let up = bindings.key_name(Action::Up);
+        let down = bindings.key_name(Action::Down);
+        let left = bindings.key_name(Action::Left);
+        let right = bindings.key_name(Action::Right);
+        let confirm = bindings.key_name(Action::Confirm);
+        let undo = bindings.key_name(Action::Undo);
+        let back = bindings.key_name(Action::Back);
+        localization.format(
+            "settings-controls",
+            &[
+                ("up", up.as_str()),
+                ("down", down.as_str()),
+                ("left", left.as_str()),
+                ("right", right.as_str()),
+                ("confirm", confirm.as_str()),
+                ("undo", undo.as_str()),
+                ("back", back.as_str()),
+            ],
+        )
+    };
+
+    let sizes = &theme.sizes;
+    let texts = [
+        (
+            localization.get("settings-title").to_string(),
+            FontRole::Title,
+            sizes.title,
+        ),
+        (lines.join("\n"), FontRole::Body, sizes.menu),
+        (controls, FontRole::Body, sizes.body),
+    ];
+    let set = |text: &mut Text, (value, role, size): &(String, FontRole, f32)| {
+        text.sections[0].value = value.clone();
+        text.sections[0].style = theme.text_style(
+            *role,
+            *size,
+            theme.palette.text,
+            &asset_server,
+            &localization,
+            &preferences,
+        );
+    };
+    // Only the selected line of the menu is read out, as the player moves
+    // to it or changes it.
+    let selected = rows[menu.selected];
+    let value = row_value(
+        selected,
+        &bindings,
+        &preferences,
+        &config,
+        &localization,
+        &manifests,
+    );
+    let selected = format!("{}: {}", row_name(selected, &localization), value);
+    let mut announce = |shown: &str, text: &str| {
+        if localization.is_ready() {
+            for changed in changed_lines(shown, text) {
+                announcements.send(Announcement(changed.to_string()));
+            }
+        }
+    };
+
+    for mut text in query.q0().iter_mut() {
+        announce(&text.sections[0].value, &texts[0].0);
+        set(&mut text, &texts[0]);
+    }
+    if localization.is_ready() && menu.announced != selected {
+        announce("", &selected);
+        menu.announced = selected;
+    }
+    for mut text in query.q1().iter_mut() {
+        set(&mut text, &texts[1]);
+    }
+    for mut text in query.q2().iter_mut() {
+        announce(&text.sections[0].value, &texts[2].0);
+        set(&mut text, &texts[2]);
+    }
+}
+
+/// Applies the saved preferences at startup. The language is applied when
+/// the locales are loaded.
+fn apply_preferences(preferences: Res<Preferences>, mut config: ResMut<GameConfig>) {
+    config.branches = preferences.branches;
+    config.difficulty = preferences.difficulty;
+    config.granularity = preferences.granularity;
+    config.decoys = preferences.decoys;
+}
+
+/// Keeps the preferences in step with what the splash screen changes too.
+fn remember_preferences(config: Res<GameConfig>, mut preferences: ResMut<Preferences>) {
+    if !config.is_changed() {
+        return;
+    }
+    // Compared first, so that the preferences are only marked as changed,
+    // and saved, when they do change.
+    if preferences.branches != config.branches {
+        preferences.branches = config.branches;
+    }
+    if preferences.difficulty != config.difficulty {
+        preferences.difficulty = config.difficulty;
+    }
+    if preferences.granularity != config.granularity {
+        preferences.granularity = config.granularity;
+    }
+    if preferences.decoys != config.decoys {
+        preferences.decoys = config.decoys;
+    }
+}
+
+fn save_preferences(preferences: Res<Preferences>) {
+    if !preferences.is_changed() || preferences.is_added() {
+        return;
+    }
+    if let Err(error) = preferences.save() {
+        warn!("Could not save the preferences: {}", error);
+    }
+}
+
+fn save_bindings(bindings: Res<Bindings>) {
+    if let Err(error) = bindings.save() {
+        warn!("Could not save the key bindings: {}", error);
+    }
+}
@@ -1,5 +1,14 @@
 use bevy::prelude::*;
 
+use super::announce::{changed_lines, Announcement};
+use super::controls::{spawn_action_button, Action, Bindings};
+use super::data::{PhrasePack, Phrases};
+use super::game::{GameConfig, GameMode, ReplayViewer};
+use super::locale::{LocaleManifest, Localization};
+use super::path::{MAX_BRANCHES, MIN_BRANCHES};
+use super::preferences::Preferences;
+use super::replay::{Replay, LAST_REPLAY};
+use super::theme::{FontRole, Theme};
 use super::{despawn_screen, GameState};
 
 pub struct SplashPlugin;
@@ -7,7 +16,15 @@ pub struct SplashPlugin;
 impl Plugin for SplashPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Splash).with_system(splash_setup))
-            .add_system_set(SystemSet::on_update(GameState::Splash).with_system(enter_game))
+            .add_system_set(
+                SystemSet::on_update(GameState::Splash)
+                    .with_system(enter_game)
+                    .with_system(pick_branches)
+                    .with_system(pick_difficulty)
+                    .with_system(pick_granularity)
+                    .with_system(pick_language)
+                    .with_system(display),
+            )
             .add_system_set(
                 SystemSet::on_exit(GameState::Splash).with_system(despawn_screen::<OnSplashScreen>),
             );
@@ -17,9 +34,22 @@ impl Plugin for SplashPlugin {
 #[derive(Component)]
 struct OnSplashScreen;
 
-fn splash_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[derive(Component)]
+struct TextStart;
+
+#[derive(Component)]
+struct TextOptions;
+
+fn splash_setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+) {
     let icon = asset_server.load("icons/logo.png");
-    let font = asset_server.load("fonts/FiraMono-Medium.ttf");
+    // Styled by `display`, as the theme and the locale may still be loading.
+    let style = TextStyle::default();
 
     commands
         .spawn_bundle(NodeBundle {
@@ -29,7 +59,7 @@ fn splash_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 align_items: AlignItems::Center,
                 ..Default::default()
             },
-            color: Color::BLACK.into(),
+            color: Color::NONE.into(),
             ..Default::default()
         })
         .insert(OnSplashScreen)
@@ -42,27 +72,231 @@ fn splash_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 image: UiImage(icon),
                 ..Default::default()
             });
-            parent.spawn_bundle(TextBundle {
-                style: Style {
-                    margin: Rect::all(Val::Px(50.0)),
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: Rect::all(Val::Px(theme.margins.text)),
+                        ..Default::default()
+                    },
+                    text: Text::with_section("", style.clone(), Default::default()),
                     ..Default::default()
-                },
-                text: Text::with_section(
-                    "Press Enter to start the seeking",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 20.0,
-                        color: Color::WHITE,
+                })
+                .insert(TextStart);
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
                     },
-                    Default::default(),
-                ),
-                ..Default::default()
-            });
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    let buttons = [
+                        ("button-start", Action::Confirm),
+                        ("button-daily", Action::Daily),
+                        ("button-codex", Action::Codex),
+                        ("button-replay", Action::Replay),
+                        ("button-settings", Action::Settings),
+                    ];
+                    for (key, action) in buttons {
+                        let style = theme.text_style(
+                            FontRole::Body,
+                            theme.sizes.info,
+                            theme.palette.text,
+                            &asset_server,
+                            &localization,
+                            &preferences,
+                        );
+                        spawn_action_button(parent, style, &theme, &localization, key, action);
+                    }
+                });
         });
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position: Rect {
+                    bottom: Val::Px(theme.margins.footer),
+                    left: Val::Px(theme.margins.footer),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            text: Text::with_section("", style, Default::default()),
+            ..Default::default()
+        })
+        .insert(OnSplashScreen)
+        .insert(TextOptions);
+}
+
+fn display(
+    asset_server: Res<AssetServer>,
+    localization: Res<Localization>,
+    manifests: Res<Assets<LocaleManifest>>,
+    config: Res<GameConfig>,
+    bindings: Res<Bindings>,
+    preferences: Res<Preferences>,
+    theme: Res<Theme>,
+    mut announcements: EventWriter<Announcement>,
+    mut query: QuerySet<(
+        QueryState<&mut Text, With<TextStart>>,
+        QueryState<&mut Text, With<TextOptions>>,
+    )>,
+) {
+    let style = |size: f32| {
+        theme.text_style(
+            FontRole::Body,
+            size,
+            theme.palette.text,
+            &asset_server,
+            &localization,
+            &preferences,
+        )
+    };
+
+    // Every line names the key bound to its action.
+    let line = |key: &str, action: Action, args: &[(&str, &str)]| {
+        let name = bindings.key_name(action);
+        let mut all_args = vec![("key", name.as_str())];
+        all_args.extend_from_slice(args);
+        localization.format(key, &all_args)
+    };
+    // The instructions are read out once the screen shows them, and then
+    // the options as they are changed.
+    let mut announce = |text: &mut Text, value: String| {
+        if localization.is_ready() {
+            for changed in changed_lines(&text.sections[0].value, &value) {
+                announcements.send(Announcement(changed.to_string()));
+            }
+        }
+        text.sections[0].value = value;
+    };
+
+    for mut text in query.q0().iter_mut() {
+        let value = [
+            line("splash-start", Action::Confirm, &[]),
+            line("splash-daily", Action::Daily, &[]),
+            line("splash-replay", Action::Replay, &[]),
+            line("splash-codex", Action::Codex, &[]),
+            line("splash-settings", Action::Settings, &[]),
+        ]
+        .join("\n");
+        announce(&mut text, value);
+        text.sections[0].style = style(theme.sizes.info);
+    }
+
+    for mut text in query.q1().iter_mut() {
+        let branches = config.branches.to_string();
+        let difficulty = localization.get(config.difficulty.key());
+        let granularity = localization.get(config.granularity.key());
+        let language = localization.locale_name(&manifests);
+        let less = bindings.key_name(Action::Left);
+        let more = bindings.key_name(Action::Right);
+        let harder = bindings.key_name(Action::Up);
+        let easier = bindings.key_name(Action::Down);
+        let value = [
+            localization.format(
+                "splash-branches",
+                &[
+                    ("branches", branches.as_str()),
+                    ("less", less.as_str()),
+                    ("more", more.as_str()),
+                ],
+            ),
+            localization.format(
+                "splash-difficulty",
+                &[
+                    ("difficulty", difficulty),
+                    ("harder", harder.as_str()),
+                    ("easier", easier.as_str()),
+                ],
+            ),
+            line(
+                "splash-granularity",
+                Action::Granularity,
+                &[("granularity", granularity)],
+            ),
+            line(
+                "splash-language",
+                Action::Language,
+                &[("language", language)],
+            ),
+        ]
+        .join("\n");
+        announce(&mut text, value);
+        text.sections[0].style = style(theme.sizes.body);
+    }
+}
+
+fn pick_branches(mut config: ResMut<GameConfig>, actions: Res<Input<Action>>) {
+    if actions.just_released(Action::Left) && config.branches > MIN_BRANCHES {
+        config.branches -= 1;
+    }
+    if actions.just_released(Action::Right) && config.branches < MAX_BRANCHES {
+        config.branches += 1;
+    }
 }
 
-fn enter_game(mut game_state: ResMut<State<GameState>>, keys: Res<Input<KeyCode>>) {
-    if keys.just_released(KeyCode::Return) {
+fn pick_difficulty(mut config: ResMut<GameConfig>, actions: Res<Input<Action>>) {
+    if actions.just_released(Action::Down) {
+        config.difficulty = config.difficulty.easier().unwrap_or(config.difficulty);
+    }
+    if actions.just_released(Action::Up) {
+        config.difficulty = config.difficulty.harder().unwrap_or(config.difficulty);
+    }
+}
+
+fn pick_granularity(mut config: ResMut<GameConfig>, actions: Res<Input<Action>>) {
+    if actions.just_released(Action::Granularity) {
+        config.granularity = config.granularity.next();
+    }
+}
+
+fn pick_language(
+    mut localization: ResMut<Localization>,
+    mut preferences: ResMut<Preferences>,
+    manifests: Res<Assets<LocaleManifest>>,
+    actions: Res<Input<Action>>,
+) {
+    if actions.just_released(Action::Language) {
+        localization.cycle_locale(&manifests);
+        preferences.language = Some(localization.requested().to_string());
+    }
+}
+
+fn enter_game(
+    mut game_state: ResMut<State<GameState>>,
+    mut config: ResMut<GameConfig>,
+    mut viewer: ResMut<ReplayViewer>,
+    actions: Res<Input<Action>>,
+    localization: Res<Localization>,
+    phrases: Res<Phrases>,
+    packs: Res<Assets<PhrasePack>>,
+) {
+    if !localization.is_ready() || phrases.get(&packs).is_none() {
+        return;
+    }
+
+    if actions.just_released(Action::Replay) {
+        match Replay::load(LAST_REPLAY) {
+            Ok(replay) => viewer.watch(replay),
+            Err(error) => warn!("Could not load the last replay: {}", error),
+        }
+    }
+    if actions.just_released(Action::Codex) {
+        game_state.set(GameState::Codex).unwrap();
+    } else if actions.just_released(Action::Settings) {
+        game_state.set(GameState::Settings).unwrap();
+    } else if viewer.is_pending() {
+        game_state.set(GameState::Replay).unwrap();
+    } else if actions.just_released(Action::Confirm) {
+        config.mode = GameMode::Random;
+        game_state.set(GameState::Game).unwrap();
+    } else if actions.just_released(Action::Daily) {
+        config.mode = GameMode::Daily;
         game_state.set(GameState::Game).unwrap();
     }
 }
@@ -0,0 +1,72 @@
+#[cfg(not(target_arch = "wasm32"))]
+const SAVE_DIR: &str = "saves";
+#[cfg(target_arch = "wasm32")]
+const KEY_PREFIX: &str = "ashgrove/";
+
+/// Reads the file saved as `name`, if there is one.
+pub fn load(name: &str) -> Option<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::fs::read_to_string(std::path::Path::new(SAVE_DIR).join(name)).ok()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        storage.get_item(&format!("{}{}", KEY_PREFIX, name)).ok()?
+    }
+}
+
+/// Saves `contents` as `name`, replacing what was saved there before.
+///
+/// Files live under `saves/` in the working directory on desktop, and in the
+/// local storage of the page on the web.
+///
+/// `name` may hold `/` to group files, e.g. `replays/last.replay.ron`.
+pub fn save(name: &str, contents: &str) -> anyhow::Result<()> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::path::Path::new(SAVE_DIR).join(name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let storage = web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| anyhow::anyhow!("local storage is unavailable"))?;
+        storage
+            .set_item(&format!("{}{}", KEY_PREFIX, name), contents)
+            .map_err(|_| anyhow::anyhow!("could not write {} to local storage", name))
+    }
+}
+
+/// Lists the files saved under the folder `dir`, with when each was last
+/// written. Only desktop saves files that can be listed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list(dir: &str) -> Vec<(String, std::time::SystemTime)> {
+    let entries = match std::fs::read_dir(std::path::Path::new(SAVE_DIR).join(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            let name = entry.file_name().into_string().ok()?;
+            Some((format!("{}/{}", dir, name), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+/// Deletes the file saved as `name`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn remove(name: &str) -> anyhow::Result<()> {
+    std::fs::remove_file(std::path::Path::new(SAVE_DIR).join(name))?;
+    Ok(())
+}
@@ -0,0 +1,406 @@
+use super::locale::Localization;
+use super::preferences::Preferences;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Deserializer};
+
+#[cfg(not(target_arch = "wasm32"))]
+const THEME_FOLDER: &str = "themes";
+/// The themes shipped with the game. Desktop builds load every theme in
+/// [`THEME_FOLDER`] instead, so that players can drop their own in.
+#[cfg(target_arch = "wasm32")]
+const SHIPPED_THEMES: [&str; 4] = [
+    "themes/dark.theme.ron",
+    "themes/light.theme.ron",
+    "themes/parchment.theme.ron",
+    "themes/terminal-green.theme.ron",
+];
+
+/// The font of every text when the dyslexia-friendly font is picked.
+const DYSLEXIC_FONT: &str = "fonts/OpenDyslexic-Regular.otf";
+
+/// Loads the themes under `assets/themes/` and keeps the [`Theme`] resource
+/// on the one picked in the preferences.
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<Theme>()
+            .add_startup_system(load_themes)
+            .add_system(apply_theme);
+    }
+}
+
+/// How every screen looks: colors, fonts, text sizes and margins.
+///
+/// Anything left out of a theme file is taken from the default dark theme.
+#[derive(Clone, Debug, Deserialize, PartialEq, TypeUuid)]
+#[uuid = "0d7e3c52-8a1f-4b6e-9f3d-2c4a5b6e7f81"]
+#[serde(default)]
+pub struct Theme {
+    /// The name the theme is picked by on the settings screen.
+    pub name: String,
+    pub palette: Palette,
+    pub fonts: Fonts,
+    pub sizes: Sizes,
+    pub margins: Margins,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_THEME.to_string(),
+            palette: Palette::default(),
+            fonts: Fonts::default(),
+            sizes: Sizes::default(),
+            margins: Margins::default(),
+        }
+    }
+}
+
+/// The theme used until the picked one is loaded, and when it is missing.
+pub const DEFAULT_THEME: &str = "dark";
+
+impl Theme {
+    /// The path of the font for `role`.
+    ///
+    /// Locales with a font of their own, such as `zh-CN`, keep it whatever
+    /// the theme says, as theme fonts may not cover their script.
+    pub fn font<'a>(&'a self, role: FontRole, localization: &'a Localization) -> &'a str {
+        if localization.has_script_font() {
+            return localization.font();
+        }
+        let font = match role {
+            FontRole::Title => &self.fonts.title,
+            FontRole::Choice => &self.fonts.choice,
+            FontRole::Body => &self.fonts.body,
+            FontRole::Hint => &self.fonts.hint,
+        };
+        font.as_deref().unwrap_or_else(|| localization.font())
+    }
+
+    /// A text style for `role`, `size` scaled to the text size picked.
+    pub fn text_style(
+        &self,
+        role: FontRole,
+        size: f32,
+        color: Color,
+        asset_server: &AssetServer,
+        localization: &Localization,
+        preferences: &Preferences,
+    ) -> TextStyle {
+        TextStyle {
+            font: asset_server.load(self.font(role, localization)),
+            font_size: preferences.font_size(size),
+            color,
+        }
+    }
+
+    /// Lays the accessibility options picked over the theme.
+    fn adapt(&mut self, preferences: &Preferences) {
+        if preferences.accessible {
+            self.palette = Palette::high_contrast();
+        }
+        if preferences.dyslexic_font {
+            self.fonts = Fonts::all(DYSLEXIC_FONT);
+        }
+    }
+}
+
+/// What a text is for, to pick its font by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FontRole {
+    Title,
+    /// The choices offered at forks.
+    Choice,
+    Body,
+    /// What hints reveal.
+    Hint,
+}
+
+/// The colors every screen is drawn with, written as hex codes such as
+/// `"#ffd700"` in theme files.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    #[serde(deserialize_with = "hex_color")]
+    pub background: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub text: Color,
+    /// The choices offered at forks.
+    #[serde(deserialize_with = "hex_color")]
+    pub choice: Color,
+    /// Text of less weight, such as words revealed by hints.
+    #[serde(deserialize_with = "hex_color")]
+    pub dim: Color,
+    /// Text of the least weight, such as blanks.
+    #[serde(deserialize_with = "hex_color")]
+    pub faint: Color,
+    /// The background of the focused choice.
+    #[serde(deserialize_with = "hex_color")]
+    pub focus: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub button: Color,
+    /// A choice flashed by a hint.
+    #[serde(deserialize_with = "hex_color")]
+    pub flash: Color,
+    /// Wrong turns, and the timer running out.
+    #[serde(deserialize_with = "hex_color")]
+    pub alert: Color,
+    /// Wrong turns later walked back.
+    #[serde(deserialize_with = "hex_color")]
+    pub dim_alert: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            choice: Color::YELLOW,
+            dim: Color::GRAY,
+            faint: Color::DARK_GRAY,
+            focus: Color::rgb(0.2, 0.2, 0.2),
+            button: Color::DARK_GRAY,
+            flash: Color::GREEN,
+            alert: Color::RED,
+            dim_alert: Color::MAROON,
+        }
+    }
+}
+
+impl Palette {
+    /// The palette of accessibility mode: light on black, with cyan and
+    /// orange in place of green and red, which many colour-blind players
+    /// cannot tell apart.
+    pub fn high_contrast() -> Self {
+        Self {
+            background: Color::BLACK,
+            text: Color::WHITE,
+            choice: Color::rgb(1.0, 1.0, 0.0),
+            dim: Color::rgb(0.85, 0.85, 0.85),
+            faint: Color::rgb(0.65, 0.65, 0.65),
+            focus: Color::rgb(0.0, 0.27, 0.8),
+            button: Color::rgb(0.0, 0.2, 0.6),
+            flash: Color::rgb(0.0, 0.9, 1.0),
+            alert: Color::rgb(1.0, 0.6, 0.0),
+            dim_alert: Color::rgb(1.0, 0.8, 0.5),
+        }
+    }
+}
+
+/// The fonts of every [`FontRole`], as paths under `assets/`. Roles left out
+/// use the font of the locale.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Fonts {
+    pub title: Option<String>,
+    pub choice: Option<String>,
+    pub body: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl Fonts {
+    /// `font` for every role.
+    fn all(font: &str) -> Self {
+        Self {
+            title: Some(font.to_string()),
+            choice: Some(font.to_string()),
+            body: Some(font.to_string()),
+            hint: Some(font.to_string()),
+        }
+    }
+}
+
+/// Text sizes before the text size picked in the settings is applied.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Sizes {
+    pub title: f32,
+    /// Footers and lists.
+    pub body: f32,
+    /// The info text of runs, the splash screen and the text walked so far.
+    pub info: f32,
+    /// The settings menu, which has many lines to fit.
+    pub menu: f32,
+    /// Choices offered letter by letter.
+    pub letter: f32,
+    /// Choices offered word by word.
+    pub word: f32,
+    /// Choices offered clause by clause.
+    pub clause: f32,
+}
+
+impl Default for Sizes {
+    fn default() -> Self {
+        Self {
+            title: 24.0,
+            body: 16.0,
+            info: 20.0,
+            menu: 14.0,
+            letter: 80.0,
+            word: 40.0,
+            clause: 24.0,
+        }
+    }
+}
+
+/// Margins, in pixels.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Margins {
+    /// Around the info text and the text walked so far.
+    pub text: f32,
+    /// Between footers and the edges of the window.
+    pub footer: f32,
+    /// Below titles.
+    pub title: f32,
+    /// Around buttons.
+    pub button: f32,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Self {
+            text: 50.0,
+            footer: 20.0,
+            title: 20.0,
+            button: 8.0,
+        }
+    }
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(hex.trim_start_matches('#'))
+        .map_err(|_| serde::de::Error::custom(format!("{} is not a hex color", hex)))
+}
+
+/// The names of the themes loaded, in the order the settings screen cycles
+/// through them.
+pub fn theme_names(themes: &Assets<Theme>) -> Vec<String> {
+    let mut names = themes
+        .iter()
+        .map(|(_, theme)| theme.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Keeps the theme files loaded, and the dyslexia-friendly font so that
+/// switching to it shows no blank frame.
+struct ThemeHandles(Vec<HandleUntyped>);
+
+#[derive(Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let theme = ron::de::from_bytes::<Theme>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(theme));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+fn load_themes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut handles = asset_server
+        .load_folder(THEME_FOLDER)
+        .unwrap_or_else(|error| {
+            warn!("Could not load the themes: {:?}", error);
+            Vec::new()
+        });
+    #[cfg(target_arch = "wasm32")]
+    let mut handles = SHIPPED_THEMES
+        .iter()
+        .map(|path| asset_server.load_untyped(*path))
+        .collect::<Vec<_>>();
+    handles.push(asset_server.load_untyped(DYSLEXIC_FONT));
+    commands.insert_resource(ThemeHandles(handles));
+}
+
+/// Switches to the theme picked once it is loaded, and to the default one if
+/// it is gone, adapted to the accessibility options.
+fn apply_theme(
+    preferences: Res<Preferences>,
+    themes: Res<Assets<Theme>>,
+    mut theme: ResMut<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    let mut picked = themes
+        .iter()
+        .map(|(_, theme)| theme)
+        .find(|theme| theme.name == preferences.theme)
+        .cloned()
+        .unwrap_or_default();
+    picked.adapt(&preferences);
+    // Compared first, so that screens only restyle on an actual change.
+    if picked != *theme {
+        *theme = picked;
+    }
+    if theme.is_changed() {
+        clear_color.0 = theme.palette.background;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_what_a_theme_file_leaves_out() {
+        let theme: Theme = ron::de::from_str(
+            r##"(
+                name: "mine",
+                palette: (background: "#102030", choice: "ffd700"),
+                fonts: (choice: Some("fonts/Mine.ttf")),
+                sizes: (letter: 96.0),
+            )"##,
+        )
+        .unwrap();
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.palette.background, Color::hex("102030").unwrap());
+        assert_eq!(theme.palette.choice, Color::hex("ffd700").unwrap());
+        assert_eq!(theme.palette.text, Color::WHITE);
+        assert_eq!(theme.fonts.choice.as_deref(), Some("fonts/Mine.ttf"));
+        assert_eq!(theme.fonts.body, None);
+        assert_eq!(theme.sizes.letter, 96.0);
+        assert_eq!(theme.sizes.title, 24.0);
+        assert_eq!(theme.margins, Margins::default());
+    }
+
+    #[test]
+    fn adapts_to_accessibility_options() {
+        let preferences = Preferences {
+            accessible: true,
+            dyslexic_font: true,
+            ..Default::default()
+        };
+        let mut theme = Theme::default();
+        theme.adapt(&preferences);
+        assert_eq!(theme.palette, Palette::high_contrast());
+        assert_eq!(theme.fonts.choice.as_deref(), Some(DYSLEXIC_FONT));
+    }
+
+    #[test]
+    fn rejects_colors_that_are_not_hex() {
+        assert!(ron::de::from_str::<Palette>(r#"(text: "white")"#).is_err());
+    }
+}
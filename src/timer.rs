@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+/// How much time every fork leaves to pick a choice.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum Difficulty {
+    /// Forks wait for as long as the player needs.
+    #[default]
+    Untimed,
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Untimed,
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+    ];
+
+    /// The key of the localized name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Difficulty::Untimed => "difficulty-untimed",
+            Difficulty::Easy => "difficulty-easy",
+            Difficulty::Normal => "difficulty-normal",
+            Difficulty::Hard => "difficulty-hard",
+        }
+    }
+
+    /// The next harder difficulty, or `None` at the hardest.
+    pub fn harder(&self) -> Option<Self> {
+        let index = Self::ALL.iter().position(|d| d == self)?;
+        Self::ALL.get(index + 1).copied()
+    }
+
+    /// The next easier difficulty, or `None` when untimed.
+    pub fn easier(&self) -> Option<Self> {
+        let index = Self::ALL.iter().position(|d| d == self)?;
+        Self::ALL.get(index.checked_sub(1)?).copied()
+    }
+
+    /// Seconds left to choose at the fork at `seeking_index`.
+    ///
+    /// The limit shrinks by a tenth every fork, down to a floor, so that
+    /// long phrases get tenser towards their end.
+    pub fn time_limit(&self, seeking_index: usize) -> Option<f32> {
+        let (start, floor) = match self {
+            Difficulty::Untimed => return None,
+            Difficulty::Easy => (8.0, 3.0),
+            Difficulty::Normal => (5.0, 2.0),
+            Difficulty::Hard => (3.0, 1.0),
+        };
+        let shrunk = start * 0.9_f32.powi(seeking_index.min(64) as i32);
+        Some(shrunk.max(floor))
+    }
+}
+
+/// The countdown of the current fork in timed mode.
+#[derive(Debug, Default)]
+pub struct ForkTimer {
+    /// Seconds the fork started with, `None` while untimed.
+    limit: Option<f32>,
+    remaining: f32,
+}
+
+impl ForkTimer {
+    /// Restarts the countdown for a new fork.
+    pub fn start(&mut self, limit: Option<f32>) {
+        self.limit = limit;
+        self.remaining = limit.unwrap_or_default();
+    }
+
+    pub fn stop(&mut self) {
+        self.start(None);
+    }
+
+    /// Counts `delta` seconds down, telling whether the time just ran out.
+    pub fn tick(&mut self, delta: f32) -> bool {
+        if self.limit.is_none() || self.remaining <= 0.0 {
+            return false;
+        }
+        self.remaining -= delta;
+        self.remaining <= 0.0
+    }
+
+    /// How much of the time is left, from 1 down to 0, or 0 while untimed.
+    pub fn fraction(&self) -> f32 {
+        match self.limit {
+            Some(limit) => (self.remaining / limit).clamp(0.0, 1.0),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limit_shrinks_along_the_path() {
+        assert_eq!(Difficulty::Untimed.time_limit(0), None);
+        let limits = (0..40)
+            .map(|index| Difficulty::Normal.time_limit(index).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(limits[0], 5.0);
+        assert!(limits.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert_eq!(limits[39], 2.0);
+        assert!(Difficulty::Hard.time_limit(3) < Difficulty::Easy.time_limit(3));
+    }
+
+    #[test]
+    fn steps_between_difficulties() {
+        assert_eq!(Difficulty::Untimed.easier(), None);
+        assert_eq!(Difficulty::Untimed.harder(), Some(Difficulty::Easy));
+        assert_eq!(Difficulty::Hard.harder(), None);
+        assert_eq!(Difficulty::Hard.easier(), Some(Difficulty::Normal));
+    }
+
+    #[test]
+    fn runs_out_once() {
+        let mut timer = ForkTimer::default();
+        timer.start(Some(1.0));
+        assert!(!timer.tick(0.6));
+        assert!((timer.fraction() - 0.4).abs() < 1e-6);
+        assert!(timer.tick(0.6));
+        assert!(!timer.tick(0.6));
+        assert_eq!(timer.fraction(), 0.0);
+    }
+
+    #[test]
+    fn untimed_never_runs_out() {
+        let mut timer = ForkTimer::default();
+        timer.start(None);
+        assert!(!timer.tick(100.0));
+        assert_eq!(timer.fraction(), 0.0);
+    }
+}
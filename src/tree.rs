@@ -0,0 +1,178 @@
+use super::path::InfoType;
+
+/// The forks of a run as the player walked them, walked-back branches
+/// included.
+#[derive(Clone, Debug, Default)]
+pub struct BranchTree {
+    steps: Vec<Step>,
+    /// The last step of the path currently walked.
+    current: Option<usize>,
+}
+
+/// A choice taken at a fork.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Every choice the fork offered, the taken one included.
+    pub choices: Vec<String>,
+    pub chosen: String,
+    /// The state the path was left in by the choice.
+    pub info_type: InfoType,
+    /// How many forks come before this one.
+    pub depth: usize,
+    parent: Option<usize>,
+}
+
+impl Step {
+    /// The choices that were not taken.
+    pub fn rejected(&self) -> impl Iterator<Item = &String> {
+        self.choices
+            .iter()
+            .filter(move |choice| **choice != self.chosen)
+    }
+
+    /// Whether the path had strayed from every phrase once this was taken.
+    pub fn is_lost(&self) -> bool {
+        matches!(self.info_type, InfoType::Neverending | InfoType::Tedious)
+    }
+}
+
+/// An unbroken run of steps, to draw as one row of the tree.
+#[derive(Debug, PartialEq)]
+pub struct Branch<'a> {
+    /// The depth of the first step, where the branch leaves its parent.
+    pub depth: usize,
+    pub steps: Vec<&'a Step>,
+    /// Whether the player walked back out of the branch.
+    pub abandoned: bool,
+}
+
+impl BranchTree {
+    pub fn choose(&mut self, choices: &[String], chosen: &str, info_type: InfoType) {
+        let depth = self
+            .current
+            .map_or(0, |parent| self.steps[parent].depth + 1);
+        self.steps.push(Step {
+            choices: choices.to_vec(),
+            chosen: chosen.to_string(),
+            info_type,
+            depth,
+            parent: self.current,
+        });
+        self.current = Some(self.steps.len() - 1);
+    }
+
+    pub fn back(&mut self) {
+        if let Some(current) = self.current {
+            self.current = self.steps[current].parent;
+        }
+    }
+
+    /// How many forks deep the tree goes.
+    pub fn depth(&self) -> usize {
+        self.steps
+            .iter()
+            .map(|step| step.depth + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Splits the tree into branches, the path currently walked first and
+    /// then the abandoned ones in the order they were left.
+    pub fn branches(&self) -> Vec<Branch<'_>> {
+        let mut drawn = vec![false; self.steps.len()];
+        let mut branches = Vec::new();
+
+        let has_child = |index: usize| self.steps.iter().any(|step| step.parent == Some(index));
+        let leaves = (0..self.steps.len()).filter(|&index| !has_child(index));
+        let current = self.current.into_iter();
+        for (leaf, abandoned) in current
+            .map(|leaf| (leaf, false))
+            .chain(leaves.map(|leaf| (leaf, true)))
+        {
+            let mut chain = Vec::new();
+            let mut next = Some(leaf);
+            while let Some(index) = next {
+                if drawn[index] {
+                    break;
+                }
+                drawn[index] = true;
+                chain.push(&self.steps[index]);
+                next = self.steps[index].parent;
+            }
+            if let Some(first) = chain.last() {
+                let depth = first.depth;
+                chain.reverse();
+                branches.push(Branch {
+                    depth,
+                    steps: chain,
+                    abandoned,
+                });
+            }
+        }
+        branches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choices(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn chosen(branch: &Branch) -> String {
+        branch
+            .steps
+            .iter()
+            .map(|step| step.chosen.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn draws_a_straight_path_as_one_branch() {
+        let mut tree = BranchTree::default();
+        tree.choose(&choices("b c"), "b", InfoType::None);
+        tree.choose(&choices("x e"), "e", InfoType::Fine);
+
+        let branches = tree.branches();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].depth, 0);
+        assert!(!branches[0].abandoned);
+        assert_eq!(chosen(&branches[0]), "be");
+        assert_eq!(branches[0].steps[1].rejected().collect::<Vec<_>>(), ["x"]);
+        assert_eq!(tree.depth(), 2);
+    }
+
+    #[test]
+    fn walked_back_steps_branch_off() {
+        let mut tree = BranchTree::default();
+        tree.choose(&choices("b c"), "b", InfoType::None);
+        tree.choose(&choices("q e"), "q", InfoType::Neverending);
+        tree.choose(&choices("k t"), "k", InfoType::Neverending);
+        tree.back();
+        tree.back();
+        tree.choose(&choices("q e"), "e", InfoType::None);
+
+        let branches = tree.branches();
+        assert_eq!(branches.len(), 2);
+        assert_eq!(chosen(&branches[0]), "be");
+        assert!(!branches[0].abandoned);
+        assert_eq!(branches[1].depth, 1);
+        assert_eq!(chosen(&branches[1]), "qk");
+        assert!(branches[1].abandoned);
+        assert!(branches[1].steps.iter().all(|step| step.is_lost()));
+    }
+
+    #[test]
+    fn walking_back_to_the_start_leaves_no_current_branch() {
+        let mut tree = BranchTree::default();
+        tree.choose(&choices("b c"), "b", InfoType::None);
+        tree.back();
+        tree.back();
+
+        let branches = tree.branches();
+        assert_eq!(branches.len(), 1);
+        assert!(branches[0].abandoned);
+    }
+}
@@ -0,0 +1,119 @@
+/// Phrases merged on their common beginnings, so that phrases starting
+/// alike are walked together until they part.
+///
+/// Nodes are keyed by the units offered at forks, lowercased. The text
+/// between them (spaces, punctuation) is left out, so that it never forks.
+#[derive(Debug)]
+pub struct PhraseTrie {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    /// The units that can follow, in the order they were first seen.
+    children: Vec<(String, usize)>,
+    /// The phrases passing through, by index.
+    phrases: Vec<usize>,
+    /// The phrases ending here, by index.
+    ends: Vec<usize>,
+}
+
+impl Default for PhraseTrie {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::default()],
+        }
+    }
+}
+
+impl PhraseTrie {
+    /// The node before any unit was walked.
+    pub const ROOT: usize = 0;
+
+    /// Adds the phrase at `index`, given as the units offered at its forks.
+    pub fn insert<I: IntoIterator<Item = String>>(&mut self, index: usize, units: I) {
+        let mut node = Self::ROOT;
+        self.nodes[node].phrases.push(index);
+        for unit in units {
+            node = match self.child(node, &unit) {
+                Some(child) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push((unit, child));
+                    child
+                }
+            };
+            self.nodes[node].phrases.push(index);
+        }
+        self.nodes[node].ends.push(index);
+    }
+
+    /// The node reached by walking `unit` from `node`, if any phrase does.
+    pub fn child(&self, node: usize, unit: &str) -> Option<usize> {
+        self.nodes[node]
+            .children
+            .iter()
+            .find(|(key, _)| key == unit)
+            .map(|&(_, child)| child)
+    }
+
+    /// The distinct units following `node`.
+    pub fn next_units(&self, node: usize) -> Vec<&str> {
+        self.nodes[node]
+            .children
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// The phrases still possible at `node`, by index.
+    pub fn phrases(&self, node: usize) -> &[usize] {
+        &self.nodes[node].phrases
+    }
+
+    /// The phrase completed at `node`, if any.
+    pub fn ended(&self, node: usize) -> Option<usize> {
+        self.nodes[node].ends.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(text: &str) -> Vec<String> {
+        text.chars().map(String::from).collect()
+    }
+
+    #[test]
+    fn shares_common_beginnings() {
+        let mut trie = PhraseTrie::default();
+        trie.insert(0, units("behealthy"));
+        trie.insert(1, units("bewealthy"));
+        trie.insert(2, units("find"));
+        assert_eq!(trie.next_units(PhraseTrie::ROOT), ["b", "f"]);
+
+        let b = trie.child(PhraseTrie::ROOT, "b").unwrap();
+        let be = trie.child(b, "e").unwrap();
+        assert_eq!(trie.phrases(be), [0, 1]);
+        assert_eq!(trie.next_units(be), ["h", "w"]);
+        assert_eq!(trie.child(be, "x"), None);
+
+        let bew = trie.child(be, "w").unwrap();
+        assert_eq!(trie.phrases(bew), [1]);
+        assert_eq!(trie.ended(bew), None);
+    }
+
+    #[test]
+    fn ends_phrases_inside_longer_ones() {
+        let mut trie = PhraseTrie::default();
+        trie.insert(0, units("bee"));
+        trie.insert(1, units("be"));
+        let b = trie.child(PhraseTrie::ROOT, "b").unwrap();
+        let be = trie.child(b, "e").unwrap();
+        assert_eq!(trie.ended(be), Some(1));
+        assert_eq!(trie.next_units(be), ["e"]);
+        assert_eq!(trie.ended(trie.child(be, "e").unwrap()), Some(0));
+    }
+}
@@ -1,8 +1,95 @@
-pub fn is_inputable_char(c: char) -> bool {
-  let c = c as u8;
-  (c >= 48 && c <= 57) || (c >= 97 && c <= 122)
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Seconds since 1970-01-01 UTC.
+pub fn unix_time() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+}
+
+/// Days since 1970-01-01 UTC.
+pub fn unix_day() -> u64 {
+    unix_time() / 86_400
+}
+
+/// Formats days since 1970-01-01 as `YYYY-MM-DD`.
+pub fn format_day(days: u64) -> String {
+    // Howard Hinnant's `civil_from_days`, for days after the epoch.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Splits `text` into graphemes, composed the same way typed input is.
+pub fn graphemes(text: &str) -> Vec<String> {
+    let text = text.nfc().collect::<String>();
+    text.graphemes(true).map(String::from).collect()
+}
+
+/// Splits `text` into words and the text between them, so that e.g.
+/// "don't stop" gives "don't", " " and "stop".
+pub fn words(text: &str) -> Vec<String> {
+    let text = text.nfc().collect::<String>();
+    text.split_word_bounds().map(String::from).collect()
+}
+
+/// Splits `text` into clauses and the punctuation and line breaks between
+/// them, so that e.g. "Rust,\nPython etc" gives "Rust", ",\n" and
+/// "Python etc".
+pub fn clauses(text: &str) -> Vec<String> {
+    const CLAUSE_BREAKS: &str = ",;:.!?\n，、。；：！？";
+
+    let mut units: Vec<String> = Vec::new();
+    let mut in_clause = false;
+    for grapheme in graphemes(text) {
+        let clause = if in_clause {
+            !grapheme.chars().any(|c| CLAUSE_BREAKS.contains(c))
+        } else {
+            is_inputable(&grapheme)
+        };
+        match units.last_mut() {
+            Some(last) if clause == in_clause => last.push_str(&grapheme),
+            _ => units.push(grapheme),
+        }
+        in_clause = clause;
+    }
+    units
+}
+
+/// Letters and numbers of any script are picked by the player, everything
+/// else (spaces, punctuation, symbols) is filled in for them.
+pub fn is_inputable(grapheme: &str) -> bool {
+    matches!(grapheme.chars().next(), Some(char) if char.is_alphanumeric())
+}
+
+/// The form a grapheme is shown in when offered as a choice.
+pub fn to_lowercase(grapheme: &str) -> String {
+    grapheme.to_lowercase()
 }
 
-pub fn to_lowercase(c: char) -> char {
-  c.to_lowercase().collect::<Vec<_>>()[0]
+/// The form used to compare typed text with the choices, so that e.g. "SS",
+/// "ß" and "ẞ" all match each other.
+pub fn fold_case(text: &str) -> String {
+    caseless::default_case_fold_str(&text.nfc().collect::<String>())
 }